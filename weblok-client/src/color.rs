@@ -0,0 +1,21 @@
+use bevy::prelude::Color;
+use weblok_common::game::Occupancy;
+
+/// Rendering colors are a client concern, the shared engine only knows about occupancies
+pub trait OccupancyColor {
+    fn color(self) -> Color;
+}
+
+impl OccupancyColor for Occupancy {
+    fn color(self) -> Color {
+        match self {
+            Occupancy::Empty => Color::rgba(0., 0., 0., 0.),
+            Occupancy::Green => Color::hex("0cca4a").unwrap(),
+            Occupancy::Red => Color::hex("fb3640").unwrap(),
+            Occupancy::Blue => Color::hex("2892d7").unwrap(),
+            Occupancy::Yellow => Color::hex("ffba49").unwrap(),
+            Occupancy::Violet => Color::hex("8e5bd6").unwrap(),
+            Occupancy::Orange => Color::hex("f77f00").unwrap(),
+        }
+    }
+}
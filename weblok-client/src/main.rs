@@ -1,5 +1,3 @@
-#![feature(variant_count)]
-#![feature(generic_const_exprs)]
 #![feature(let_chains)]
 
 mod comms;
@@ -8,8 +6,9 @@ mod comms;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::{app::AppExit, input::mouse::MouseWheel, prelude::*, window::PresentMode};
 
-mod game;
-use game::*;
+use weblok_common::game::*;
+mod color;
+use color::*;
 mod ui_state;
 use ui_state::*;
 mod log;
@@ -18,6 +17,9 @@ use log::*;
 #[derive(Component)]
 struct UnplacedPiece(i8, i8);
 
+#[derive(Component)]
+struct PlacedTile;
+
 fn close_on_esc(key_input: Res<Input<KeyCode>>, mut exit: EventWriter<AppExit>) {
     if key_input.just_released(KeyCode::Escape) {
         exit.send(AppExit);
@@ -29,22 +31,22 @@ fn place_piece_system(
     mut query: Query<(&mut Transform, &UnplacedPiece), With<UnplacedPiece>>,
     mut ui_state: ResMut<UiState>,
     windows: Res<Windows>,
-    mut board: ResMut<Board>,
+    game: Res<GameState>,
 ) {
     let (_, win_height) = get_window_dims(&windows);
 
-    let piece_offsets = ui_state.piece.offsets(ui_state.rotation);
+    let piece_offsets = ui_state.piece.offsets(ui_state.orientation);
     let pivot = piece_offsets.pivot;
 
     for ev in cursor_evr.iter() {
         let (mouse_board_coords, snap_place_pos) =
-            snap_piece_if_in_grid(&ui_state, ev.position, win_height, pivot);
+            snap_piece_if_in_grid(&ui_state, ev.position, win_height, pivot, game.board().size());
         ui_state.mouse_board_coords = mouse_board_coords;
 
         let place_pos = match mouse_board_coords {
             Some((col, row)) => {
-                let coords = coords_for_placement(ui_state.piece, ui_state.rotation, col, row);
-                if board.is_placement_valid(ui_state.occupancy, &coords) {
+                let coords = coords_for_placement(ui_state.piece, ui_state.orientation, col, row);
+                if game.board().is_placement_valid(ui_state.occupancy, &coords) {
                     snap_place_pos
                 } else {
                     ev.position
@@ -65,6 +67,7 @@ fn snap_piece_if_in_grid(
     ev_position: Vec2,
     win_height: f32,
     pivot: (i8, i8),
+    board_size: usize,
 ) -> (Option<(i8, i8)>, Vec2) {
     let magic_size = ui_state.tile_size + ui_state.tile_padding;
     let snap_dist = 0.2;
@@ -76,7 +79,8 @@ fn snap_piece_if_in_grid(
 
     let round_x = possy.x.round();
     let round_y = possy.y.round();
-    if round_x >= 0.0 && round_x < 20.0 && round_y >= 0.0 && round_y < 20.0 {
+    let board_size = board_size as f32;
+    if round_x >= 0.0 && round_x < board_size && round_y >= 0.0 && round_y < board_size {
         if (possy.x - round_x).abs() <= snap_dist && (possy.y - round_y).abs() <= snap_dist {
             let coords = (round_x as i8 - pivot.0, round_y as i8 - pivot.1);
 
@@ -117,7 +121,7 @@ fn spawn_piece(commands: &mut Commands, ui_state: &UiState, windows: &Windows) {
         return;
     };
 
-    let PieceOffsets { offsets, pivot } = ui_state.piece.offsets(ui_state.rotation);
+    let PieceOffsets { offsets, pivot } = ui_state.piece.offsets(ui_state.orientation);
 
     for (x, y) in offsets {
         let [h, s, l, _] = ui_state.occupancy.color().as_hlsa_f32();
@@ -143,28 +147,40 @@ fn pizz_system(
     mut event_reader: EventReader<MouseWheel>,
     mut ui_state: ResMut<UiState>,
     unplaced_entities: Query<Entity, With<UnplacedPiece>>,
+    placed_entities: Query<Entity, With<PlacedTile>>,
     windows: Res<Windows>,
-    mut board: ResMut<Board>,
+    mut game: ResMut<GameState>,
 ) {
-    if key_input.just_released(KeyCode::Tab) {
-        ui_state.next_selected_occupancy();
+    let undone = key_input.just_released(KeyCode::U) && game.undo();
+    let redone = key_input.just_released(KeyCode::Y) && game.redo();
+    if undone || redone {
+        redraw_placed_tiles(&mut commands, &game, ui_state.tile_size, placed_entities.iter());
+        ui_state.follow_turn(&game);
+        replace_selected_piece(&mut commands, &ui_state, unplaced_entities.iter(), &windows);
+    }
+    if key_input.just_released(KeyCode::P) && game.pass(ui_state.occupancy).is_ok() {
+        save_replay_if_over(&game);
+        ui_state.follow_turn(&game);
         replace_selected_piece(&mut commands, &ui_state, unplaced_entities.iter(), &windows);
     }
     if key_input.just_released(KeyCode::R) {
         ui_state.next_selected_rotation();
         replace_selected_piece(&mut commands, &ui_state, unplaced_entities.iter(), &windows);
     }
+    if key_input.just_released(KeyCode::F) {
+        ui_state.flip_selected_orientation();
+        replace_selected_piece(&mut commands, &ui_state, unplaced_entities.iter(), &windows);
+    }
     if mouse_input.just_released(MouseButton::Left) && let Some((col, row)) = ui_state.mouse_board_coords {
-        if board.place(
-            ui_state.occupancy,
-            ui_state.piece,
-            ui_state.rotation,
-            col,
-            row,
-        ) {
-            for (x, y) in coords_for_placement(ui_state.piece, ui_state.rotation, col, row) {
-                spawn_tile(&mut commands, &mut board, ui_state.tile_size, x, y, 2.);
+        let mv = Move::new(ui_state.piece, ui_state.orientation, col, row);
+        if game.play(ui_state.occupancy, mv).is_ok() {
+            for (x, y) in mv.coords() {
+                let tile = spawn_tile(&mut commands, game.board(), ui_state.tile_size, x, y, 2.);
+                commands.entity(tile).insert(PlacedTile);
             }
+            save_replay_if_over(&game);
+            ui_state.follow_turn(&game);
+            replace_selected_piece(&mut commands, &ui_state, unplaced_entities.iter(), &windows);
         }
     }
 
@@ -184,16 +200,15 @@ fn setup(mut commands: Commands, windows: Res<Windows>) {
     let camera = OrthographicCameraBundle::new_2d();
     commands.spawn_bundle(camera);
 
-    let mut board = Board::new();
-    board.place(Occupancy::Green, Piece::FiveU, Rotation::OneEighty, 0, 0);
-    board.place(Occupancy::Green, Piece::One, Rotation::OneEighty, 3, 2);
+    let game = GameState::new();
 
-    let ui_state = UiState::new();
-    for y in 0..DIM {
-        for x in 0..DIM {
+    let mut ui_state = UiState::new();
+    ui_state.follow_turn(&game);
+    for y in 0..game.board().size() {
+        for x in 0..game.board().size() {
             spawn_tile(
                 &mut commands,
-                &mut board,
+                game.board(),
                 ui_state.tile_size,
                 x as i8,
                 y as i8,
@@ -204,11 +219,34 @@ fn setup(mut commands: Commands, windows: Res<Windows>) {
 
     spawn_piece(&mut commands, &ui_state, &windows);
 
-    commands.insert_resource(board);
+    commands.insert_resource(game);
     commands.insert_resource(ui_state);
 }
 
-fn spawn_tile(commands: &mut Commands, board: &mut Board, tile_size: f32, x: i8, y: i8, z: f32) {
+fn save_replay_if_over(game: &GameState) {
+    if let Err(err) = replay::save_if_over(game, &[]) {
+        bevy_log(&format!("Could not save the replay: {}", err));
+    }
+}
+
+fn redraw_placed_tiles(
+    commands: &mut Commands,
+    game: &GameState,
+    tile_size: f32,
+    placed_entities: impl Iterator<Item = Entity>,
+) {
+    for entity in placed_entities {
+        commands.entity(entity).despawn();
+    }
+    for placement in game.board().placements() {
+        for (x, y) in placement.mv.coords() {
+            let tile = spawn_tile(commands, game.board(), tile_size, x, y, 2.);
+            commands.entity(tile).insert(PlacedTile);
+        }
+    }
+}
+
+fn spawn_tile<const D: usize>(commands: &mut Commands, board: &Board<D>, tile_size: f32, x: i8, y: i8, z: f32) -> Entity {
     let color = match board.occupancies.get(x as i8, y as i8) {
         Occupancy::Empty => Color::DARK_GRAY,
         other => other.color(),
@@ -221,7 +259,7 @@ fn spawn_tile(commands: &mut Commands, board: &mut Board, tile_size: f32, x: i8,
             ..Default::default()
         },
         ..Default::default()
-    });
+    }).id()
 }
 
 */
@@ -247,6 +285,10 @@ type Wss = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
 mod log;
 use log::bevy_log;
+#[cfg(not(target_arch = "wasm32"))]
+mod play;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
 use tokio::{io::AsyncBufReadExt, net::TcpStream};
 use tokio_tungstenite::{WebSocketStream, MaybeTlsStream};
 use weblok_common::*;
@@ -254,6 +296,29 @@ use weblok_common::*;
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match &args[1..] {
+        [flag, path] if flag == "--replay" => {
+            if let Err(err) = replay::run(path) {
+                eprintln!("{}", err);
+            }
+            return;
+        }
+        [flag, variant @ ..] if flag == "--play" => {
+            let variant = match variant {
+                [] => Ok(game::Variant::Classic),
+                [variant] => variant.parse().map_err(|err: game::NotationError| err.to_string()),
+                _ => Err("usage: --play [Classic|Duo]".to_string()),
+            };
+            let played = variant.and_then(|variant| play::run(variant).map_err(|err| err.to_string()));
+            if let Err(err) = played {
+                eprintln!("{}", err);
+            }
+            return;
+        }
+        _ => {}
+    }
+
     let (mut ws_stream, _) = connect_async(SERVER_URL).await.expect("Failed to connect");
 
     let stdin = tokio::io::stdin();
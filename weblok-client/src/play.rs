@@ -0,0 +1,72 @@
+use std::io::{self, BufRead, Write};
+
+use weblok_common::{game::*, utils};
+
+use crate::replay;
+
+/// A game on the console for players sharing the keyboard, saved as a replay at the end
+/// Moves are typed without the color, e.g. `FiveX R0 a1`, or `pass`, `undo`, `redo`, `quit`
+pub fn run(variant: Variant) -> io::Result<()> {
+    match variant {
+        Variant::Classic => play::<CLASSIC_DIM>(variant),
+        Variant::Duo => play::<DUO_DIM>(variant),
+    }
+}
+
+fn play<const D: usize>(variant: Variant) -> io::Result<()> {
+    let mut game: GameState<D> = GameState::with_variant(variant);
+    let players: Vec<_> = game
+        .turn_order()
+        .iter()
+        .map(|occupancy| (*occupancy, utils::generate_username()))
+        .collect();
+
+    let mut lines = io::stdin().lock().lines();
+    while let Some(occupancy) = game.current_player() {
+        println!("\n{}", game.board().occupancies);
+        let name = players
+            .iter()
+            .find(|(o, _)| *o == occupancy)
+            .map_or("", |(_, n)| n);
+        print!("{occupancy} ({name})> ");
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let played = match line?.trim() {
+            "quit" => return Ok(()),
+            "undo" => game
+                .undo()
+                .then_some(())
+                .ok_or("nothing to undo".to_string()),
+            "redo" => game
+                .redo()
+                .then_some(())
+                .ok_or("nothing to redo".to_string()),
+            turn => match format!("{occupancy} {turn}").parse() {
+                Ok(Turn::Placed { occupancy, mv }) => {
+                    game.play(occupancy, mv).map_err(|err| err.to_string())
+                }
+                Ok(Turn::Passed { occupancy, .. }) => {
+                    game.pass(occupancy).map_err(|err| err.to_string())
+                }
+                Err(err) => Err(err.to_string()),
+            },
+        };
+        if let Err(err) = played {
+            println!("{err}");
+        }
+    }
+
+    println!("\n{}", game.board().occupancies);
+    if let Some(result) = game.result(variant.scoring()) {
+        for (occupancy, score) in &result.scores {
+            println!("{occupancy}: {score}");
+        }
+    }
+    if let Some(path) = replay::save_if_over(&game, &players)? {
+        println!("Saved the replay to {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use weblok_common::game::*;
+
+const REPLAY_DIR: &str = "replays";
+
+/// Writes a finished game to replays/<unix time>.wblk, None if it is not over yet
+pub fn save_if_over<const D: usize>(
+    game: &GameState<D>,
+    players: &[(Occupancy, String)],
+) -> io::Result<Option<PathBuf>> {
+    if !game.is_over() {
+        return Ok(None);
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut record = GameRecord::from_game(game);
+    record.players = players.to_vec();
+    record.date = Some(utc_date(secs));
+
+    let path = PathBuf::from(format!("{REPLAY_DIR}/{secs}.{REPLAY_EXTENSION}"));
+    fs::create_dir_all(REPLAY_DIR)?;
+    record.save(&path)?;
+    Ok(Some(path))
+}
+
+/// YYYY-MM-DD from seconds since the epoch, after Howard Hinnant's civil_from_days
+fn utc_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Steps through a replay file on the console
+/// Enter or `n` steps forward, `p` steps back, a number jumps to that ply and `q` quits
+pub fn run(path: &str) -> Result<(), ReplayError> {
+    let record = GameRecord::load(path)?;
+    match record.variant {
+        Variant::Classic => play::<CLASSIC_DIM>(record),
+        Variant::Duo => play::<DUO_DIM>(record),
+    }
+}
+
+fn play<const D: usize>(record: GameRecord) -> Result<(), ReplayError> {
+    let mut replay: Replay<D> = Replay::new(record)?;
+    print_headers(replay.record());
+    print_position(&replay);
+
+    for line in io::stdin().lock().lines() {
+        match line?.trim() {
+            "" | "n" => {
+                replay.step_forward();
+            }
+            "p" => {
+                replay.step_back();
+            }
+            "q" => break,
+            other => match other.parse() {
+                Ok(ply) => replay.jump_to(ply),
+                Err(_) => {
+                    println!("Enter or n: next, p: previous, <ply>: jump, q: quit");
+                    continue;
+                }
+            },
+        }
+        print_position(&replay);
+    }
+    Ok(())
+}
+
+fn print_headers(record: &GameRecord) {
+    println!("{:?}", record.variant);
+    for occupancy in &record.turn_order {
+        println!("{occupancy}: {}", record.player(*occupancy).unwrap_or("?"));
+    }
+    if let Some(date) = &record.date {
+        println!("Played {date}");
+    }
+}
+
+fn print_position<const D: usize>(replay: &Replay<D>) {
+    println!();
+    println!("{}", replay.game().board().occupancies);
+    match replay.last_turn() {
+        Some(turn) => println!("{}/{}: {turn}", replay.ply(), replay.len()),
+        None => println!("0/{}", replay.len()),
+    }
+    if replay.is_at_end() {
+        if let Some(result) = &replay.record().result {
+            let scores: Vec<_> = result
+                .scores
+                .iter()
+                .map(|(occupancy, score)| format!("{occupancy} {score}"))
+                .collect();
+            println!("Result: {}", scores.join(", "));
+        }
+    }
+}
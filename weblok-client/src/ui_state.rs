@@ -1,15 +1,11 @@
-use std::mem::transmute;
-
 use bevy::{prelude::Transform, math::Vec2};
 
-use crate::game::{Piece, Occupancy, Rotation};
-
-const PIECE_COUNT: u8 = std::mem::variant_count::<Piece>() as u8;
+use weblok_common::game::{GameState, Piece, Occupancy, Orientation};
 
 pub struct UiState {
     pub piece: Piece,
     pub occupancy: Occupancy,
-    pub rotation: Rotation,
+    pub orientation: Orientation,
     pub window_center_x: f32,
     pub window_center_y: f32,
     pub tile_size: f32,
@@ -22,7 +18,7 @@ impl UiState {
         Self {
             piece: Piece::One,
             occupancy: Occupancy::Green,
-            rotation: Rotation::Zero,
+            orientation: Orientation::default(),
             window_center_x: -500.,
             window_center_y: -500.,
             tile_size: 20.,
@@ -32,33 +28,26 @@ impl UiState {
     }
 
     pub fn next_selected_piece(&mut self) {
-        self.piece = unsafe {
-            transmute((self.piece as u8 + 1) % PIECE_COUNT)
-        };
+        self.piece = self.piece.next();
     }
 
     pub fn prev_selected_piece(&mut self) {
-        self.piece = unsafe { transmute(
-            if self.piece == Piece::One {
-                PIECE_COUNT - 1
-            } else {
-                ((self.piece as u8 - 1) % PIECE_COUNT) as u8
-            }
-        )};
+        self.piece = self.piece.prev();
     }
 
-    pub fn next_selected_occupancy(&mut self) {
-        self.occupancy = match self.occupancy {
-            Occupancy::Empty  => unreachable!(),
-            Occupancy::Green  => Occupancy::Red,
-            Occupancy::Red    => Occupancy::Blue,
-            Occupancy::Blue   => Occupancy::Yellow,
-            Occupancy::Yellow => Occupancy::Green,
-        };
+    /// Selects the occupancy whose turn it is
+    pub fn follow_turn(&mut self, game: &GameState) {
+        if let Some(occupancy) = game.current_player() {
+            self.occupancy = occupancy;
+        }
     }
 
     pub fn next_selected_rotation(&mut self) {
-        self.rotation = self.rotation.next_clockwise();
+        self.orientation = self.orientation.next_clockwise();
+    }
+
+    pub fn flip_selected_orientation(&mut self) {
+        self.orientation = self.orientation.flip();
     }
 
     pub fn tile_transform(
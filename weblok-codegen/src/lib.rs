@@ -1,6 +1,15 @@
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse::Parse, parse_macro_input, punctuated::Punctuated, token::Comma, Ident};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::Parse, parse_macro_input, punctuated::Punctuated, spanned::Spanned, token::Comma, Data,
+    DeriveInput, Fields, Ident, Type,
+};
+
+use weblok_shapes::{MaskBits, Shape, ORIENTATION_COUNT};
+
+/// `crate::game::Rotation` variants, in the order [`Shape`] lists orientations
+const ROTATIONS: [&str; 4] = ["Zero", "Ninety", "OneEighty", "TwoSeventy"];
 
 struct Arguments(Vec<Ident>);
 
@@ -13,133 +22,276 @@ impl Parse for Arguments {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum PieceChar {
-    Solid,
-    Pivot,
-    Empty,
-}
-
+/// Expands to a `crate::game::PieceTable` for the mask, e.g. `piece!(XOX, _X_)`
+/// `X` is a square, `O` the square the piece is held by and `_` is empty
 #[proc_macro]
 pub fn piece(input: TokenStream) -> TokenStream {
     let Arguments(args_parsed) = parse_macro_input!(input as Arguments);
 
-    let rot = &args_parsed[0];
+    match piece_table(&args_parsed) {
+        Ok(table) => TokenStream::from(table),
+        Err(e) => TokenStream::from(e),
+    }
+}
 
-    let mask_zero_res: Result<Vec<Vec<_>>, TokenStream> = args_parsed[1..]
-        .iter()
-        .map(|id| {
-            let s = id.to_string();
-
-            s.chars()
-                .map(|c| match c {
-                    'X' => Ok(PieceChar::Solid),
-                    'O' => Ok(PieceChar::Pivot),
-                    '_' => Ok(PieceChar::Empty),
-                    c => Err(make_error(&format!("Invalid char {c}"))),
-                })
-                .collect()
-        })
-        .collect();
+/// Generates a piece enum from the masks of its variants, along with
+/// - `ALL`, every piece in declaration order, and `iter()` over it
+/// - `index()`, the position in `ALL`
+/// - `next()` and `prev()`, cycling through `ALL`
+/// - `square_count()`
+/// - `table()` and `offsets()`, as built by [`piece!`]
+///
+/// ```ignore
+/// piece_set! {
+///     #[derive(Clone, Copy, Debug)]
+///     pub enum Piece {
+///         One(O),
+///         ThreeL(OX, X_),
+///         #[custom]
+///         Custom(CustomPiece),
+///     }
+/// }
+/// ```
+///
+/// A variant marked `#[custom]` holds a piece defined at runtime. It is left out of `ALL`,
+/// `next()` and `prev()` leave it alone, and the other methods are forwarded to its
+/// `square_count()` and `table()`.
+#[proc_macro]
+pub fn piece_set(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
 
-    if let Err(e) = mask_zero_res {
-        return e;
+    match piece_set_impl(input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e),
     }
-    let mask_zero = mask_zero_res.unwrap();
+}
 
-    let expected_len = mask_zero
-        .iter()
-        .max_by_key(|row| row.len())
-        .unwrap_or(&vec![])
-        .len();
-    let is_valid = mask_zero
-        .iter()
-        .map(|row| row.len())
-        .rfold(true, |acc, it| acc && expected_len == it);
+fn piece_set_impl(input: DeriveInput) -> Result<TokenStream2, TokenStream2> {
+    let DeriveInput {
+        attrs,
+        vis,
+        ident: name,
+        data,
+        ..
+    } = input;
+    let Data::Enum(data) = data else {
+        return Err(make_error(name.span(), "piece_set! expects an enum"));
+    };
 
-    if !is_valid {
-        return make_error(&format!("Expected every row to be {} long", expected_len));
-    }
+    let mut variants = vec![];
+    let mut pieces = vec![];
+    let mut custom = None;
 
-    let mask_ninety = rot_piece_90(&mask_zero);
-    let mask_one_eighty = rot_piece_180(&mask_zero);
-    let mask_two_seventy = rot_piece_180(&rot_piece_90(&mask_zero));
-
-    // NOW begins the fun
-    let (coords_zero, pivot_zero) = mask_to_coords(&mask_zero);
-    let (coords_ninety, pivot_ninety) = mask_to_coords(&mask_ninety);
-    let (coords_one_eighty, pivot_one_eighty) = mask_to_coords(&mask_one_eighty);
-    let (coords_two_seventy, pivot_two_seventy) = mask_to_coords(&mask_two_seventy);
-
-    let expanded = quote! {
-        match #rot {
-            Rotation::Zero => crate::game::PieceOffsets {
-                offsets: vec![#(#coords_zero,)*],
-                pivot: #pivot_zero,
-            },
-            Rotation::Ninety => crate::game::PieceOffsets {
-                offsets: vec![#(#coords_ninety,)*],
-                pivot: #pivot_ninety,
-            },
-            Rotation::OneEighty => crate::game::PieceOffsets {
-                offsets: vec![#(#coords_one_eighty,)*],
-                pivot: #pivot_one_eighty,
-            },
-            Rotation::TwoSeventy => crate::game::PieceOffsets {
-                offsets: vec![#(#coords_two_seventy,)*],
-                pivot: #pivot_two_seventy,
+    for mut variant in data.variants {
+        let is_custom = variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("custom"));
+        variant.attrs.retain(|attr| !attr.path.is_ident("custom"));
+        let Fields::Unnamed(fields) = &variant.fields else {
+            return Err(make_error(
+                variant.ident.span(),
+                "Expected the variant's mask in parentheses",
+            ));
+        };
+
+        if is_custom {
+            if custom.is_some() || fields.unnamed.len() != 1 {
+                return Err(make_error(
+                    variant.ident.span(),
+                    "Expected a single custom variant with one field",
+                ));
             }
+            custom = Some(variant.ident.clone());
+        } else {
+            let rows = fields
+                .unnamed
+                .iter()
+                .map(|field| match &field.ty {
+                    Type::Path(path) if path.qself.is_none() => path
+                        .path
+                        .get_ident()
+                        .cloned()
+                        .ok_or_else(|| make_error(path.span(), "Expected a mask row")),
+                    ty => Err(make_error(ty.span(), "Expected a mask row")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let square_count = rows
+                .iter()
+                .flat_map(|row| row.to_string().chars().collect::<Vec<_>>())
+                .filter(|c| *c != '_')
+                .count() as u8;
+            pieces.push((variant.ident.clone(), piece_table(&rows)?, square_count));
+
+            variant.fields = Fields::Unit;
         }
-    };
+        variants.push(variant);
+    }
 
-    TokenStream::from(expanded)
-}
+    let piece_count = pieces.len();
+    let idents: Vec<_> = pieces.iter().map(|(ident, _, _)| ident).collect();
+    let indices = 0..piece_count;
+    let tables = pieces.iter().map(|(_, table, _)| table);
+    let square_counts = pieces.iter().map(|(_, _, count)| count);
+
+    let (index_arm, square_count_arm, table_arm): (TokenStream2, _, _) = custom
+        .map(|custom| {
+            (
+                quote! { #name::#custom(_) => None, },
+                quote! { #name::#custom(custom) => custom.square_count(), },
+                quote! { #name::#custom(custom) => custom.table(), },
+            )
+        })
+        .unwrap_or_default();
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis enum #name {
+            #(#variants,)*
+        }
+
+        impl #name {
+            /// Every piece in declaration order
+            pub const ALL: [#name; #piece_count] = [#(#name::#idents,)*];
+
+            pub fn iter() -> impl Iterator<Item = #name> {
+                #name::ALL.into_iter()
+            }
+
+            /// Position in [`Self::ALL`]
+            pub fn index(self) -> Option<usize> {
+                match self {
+                    #(#name::#idents => Some(#indices),)*
+                    #index_arm
+                }
+            }
+
+            /// The piece after this one in [`Self::ALL`], wrapping around
+            pub fn next(self) -> #name {
+                match self.index() {
+                    Some(i) => #name::ALL[(i + 1) % #piece_count],
+                    None => self,
+                }
+            }
+
+            /// The piece before this one in [`Self::ALL`], wrapping around
+            pub fn prev(self) -> #name {
+                match self.index() {
+                    Some(i) => #name::ALL[(i + #piece_count - 1) % #piece_count],
+                    None => self,
+                }
+            }
 
-fn mask_to_coords(piece_map: &Vec<Vec<PieceChar>>) -> (Vec<Coord>, Coord) {
-    let col_count = piece_map[0].len();
-    let row_count = piece_map.len();
-    let mut coords = vec![];
-    let mut pivot = Coord((0, 0));
+            /// Number of unit squares in the piece
+            pub fn square_count(self) -> u8 {
+                match self {
+                    #(#name::#idents => #square_counts,)*
+                    #square_count_arm
+                }
+            }
 
-    for col in 0..col_count {
-        for row in 0..row_count {
-            if piece_map[row][col] != PieceChar::Empty {
-                coords.push(Coord((col as i8, row as i8)));
+            /// Every orientation of the piece
+            pub fn table(self) -> &'static crate::game::PieceTable {
+                match self {
+                    #(#name::#idents => &#tables,)*
+                    #table_arm
+                }
             }
-            if piece_map[row][col] == PieceChar::Pivot {
-                pivot = Coord((col as i8, row as i8));
+
+            /// @return (x,y)
+            pub fn offsets(self, orientation: crate::game::Orientation) -> crate::game::PieceOffsets {
+                self.table().offsets(orientation)
             }
         }
-    }
-    (coords, pivot)
+    })
 }
 
-/// Requires all the strs to be the same length
-fn rot_piece_90<T: Copy>(piece_map: &Vec<Vec<T>>) -> Vec<Vec<T>> {
-    let col_count = piece_map[0].len();
-    let row_count = piece_map.len();
-    let mut output = vec![Vec::with_capacity(row_count); col_count];
+/// Checks the mask before building the table, errors point at the offending row
+fn piece_table(rows: &[Ident]) -> Result<TokenStream2, TokenStream2> {
+    let names: Vec<String> = rows.iter().map(Ident::to_string).collect();
+    let shape = Shape::parse(&names).map_err(|e| {
+        let span = rows.get(e.row()).map_or_else(Span::call_site, Ident::span);
+        make_error(span, &e.to_string())
+    })?;
+
+    let orientations: Vec<OrientationTokens> = (0..ORIENTATION_COUNT)
+        .map(|i| OrientationTokens {
+            rotation: format_ident!("{}", ROTATIONS[i % ROTATIONS.len()]),
+            flipped: i >= ROTATIONS.len(),
+        })
+        .collect();
+    // Symmetric pieces look the same in several orientations, each of those maps to the
+    // first one in Orientation::ALL order
+    let canonical = shape.canonical.iter().map(|i| &orientations[*i]);
+    let unique = (0..ORIENTATION_COUNT)
+        .filter(|i| shape.canonical[*i] == *i)
+        .map(|i| &orientations[i]);
+
+    let offsets = shape.orientations.iter().map(|orientation| {
+        let coords = orientation.offsets.iter().map(|offset| Coord(*offset));
+        quote! { &[#(#coords,)*] }
+    });
+    let pivots = shape
+        .orientations
+        .iter()
+        .map(|orientation| Coord(orientation.pivot));
+    let masks = shape
+        .orientations
+        .iter()
+        .map(|orientation| mask_tokens(&MaskBits::from_offsets(&orientation.offsets)));
 
-    for col in (0..col_count).rev() {
-        for row in piece_map.iter() {
-            output[col_count - 1 - col].push(row[col]);
+    Ok(quote! {
+        crate::game::PieceTable {
+            orientations: &[#(#unique,)*],
+            canonical: [#(#canonical,)*],
+            offsets: [#(#offsets,)*],
+            pivots: [#(#pivots,)*],
+            masks: [#(#masks,)*],
         }
-    }
-    output
+    })
 }
 
-fn rot_piece_180<T: Copy>(piece_map: &Vec<Vec<T>>) -> Vec<Vec<T>> {
-    piece_map
-        .iter()
-        .map(|s| s.iter().rev().map(|x| *x).collect())
-        .rev()
-        .collect()
+/// A `crate::game::PieceMask`, see there for the layout
+fn mask_tokens(mask: &MaskBits) -> TokenStream2 {
+    let MaskBits {
+        rows,
+        side_neighbors,
+        corner_neighbors,
+        width,
+        height,
+    } = mask;
+    quote! {
+        crate::game::PieceMask {
+            rows: [#(#rows,)*],
+            side_neighbors: [#(#side_neighbors,)*],
+            corner_neighbors: [#(#corner_neighbors,)*],
+            width: #width,
+            height: #height,
+        }
+    }
 }
 
 struct Coord((i8, i8));
 
+struct OrientationTokens {
+    rotation: Ident,
+    flipped: bool,
+}
+
+impl ToTokens for OrientationTokens {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let OrientationTokens { rotation, flipped } = self;
+        tokens.extend(quote! {
+            crate::game::Orientation {
+                rotation: crate::game::Rotation::#rotation,
+                flipped: #flipped,
+            }
+        });
+    }
+}
+
 impl ToTokens for Coord {
-    fn to_tokens(&self, tokens: &mut quote::__private::TokenStream) {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
         let Coord((zero, one)) = self;
         tokens.extend(quote! {
             (#zero, #one)
@@ -147,6 +299,6 @@ impl ToTokens for Coord {
     }
 }
 
-fn make_error(message: &str) -> TokenStream {
-    TokenStream::from(quote!(compile_error!(#message)))
+fn make_error(span: Span, message: &str) -> TokenStream2 {
+    syn::Error::new(span, message).to_compile_error()
 }
@@ -0,0 +1,6 @@
+/// Each file under tests/ui has a mask `piece!` should reject, with the error it gives
+#[test]
+fn invalid_masks() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
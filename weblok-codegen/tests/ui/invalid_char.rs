@@ -0,0 +1,5 @@
+use weblok_codegen::piece;
+
+fn main() {
+    let _ = piece!(XO, XA);
+}
@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
 
+use crate::game::GameResult;
+
 pub use bincode;
 
 /// Sending from the client to the server
@@ -21,4 +23,5 @@ pub enum ServerMessage {
     BroadcastChatMessage(SystemTime, String, String),
     GetRoomsResponse(Vec<String>),
     JoinRoomResponse(Result<(), String>),
+    GameFinished(GameResult),
 }
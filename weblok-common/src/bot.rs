@@ -0,0 +1,72 @@
+//! Computer players
+//!
+//! Bots that search on several threads give the same results for the same config and
+//! seed, however the threads happen to be scheduled.
+
+use std::time::Instant;
+
+use crate::game::*;
+
+mod endgame;
+pub use self::endgame::*;
+mod greedy;
+pub use self::greedy::*;
+mod mcts;
+pub use self::mcts::*;
+mod minimax;
+pub use self::minimax::*;
+mod random;
+pub use self::random::*;
+
+pub trait Bot<const D: usize = CLASSIC_DIM> {
+    /// The move to play for the current player, None to pass
+    fn choose_move(&mut self, game: &GameState<D>) -> Option<Move>;
+}
+
+/// Plays the bot's move for the current player, or passes if it has none
+pub fn play_turn<const D: usize>(
+    bot: &mut (impl Bot<D> + ?Sized),
+    game: &mut GameState<D>,
+) -> Result<(), MoveError> {
+    let occupancy = game.current_player().ok_or(MoveError::GameOver)?;
+    match bot.choose_move(game) {
+        Some(mv) => game.play(occupancy, mv),
+        None => game.pass(occupancy),
+    }
+}
+
+/// Calls `search` on every state, on separate threads where the target has them
+///
+/// Results come back in the order of `states`, so they never depend on scheduling.
+fn search_in_parallel<S: Send, T: Send>(
+    states: &mut [S],
+    search: impl Fn(&mut S) -> T + Send + Sync,
+) -> Vec<T> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        use rayon::prelude::*;
+        states.par_iter_mut().map(search).collect()
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        states.iter_mut().map(search).collect()
+    }
+}
+
+/// A search ran past its deadline
+struct OutOfTime;
+
+/// Checking the clock every node would slow searches down
+const NODES_PER_CLOCK_CHECK: u64 = 1024;
+
+/// Called with the running node count, fails every so often once the deadline has passed
+fn check_clock(nodes: u64, deadline: Option<Instant>) -> Result<(), OutOfTime> {
+    match deadline {
+        Some(deadline)
+            if nodes.is_multiple_of(NODES_PER_CLOCK_CHECK) && Instant::now() >= deadline =>
+        {
+            Err(OutOfTime)
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::*;
+
+/// The [`endgame_complexity`] at which [`MctsConfig`] and [`MinimaxConfig`] switch to
+/// [`solve_endgame`]'s moves by default
+/// Bots keep no stats for the moves the solver picks.
+pub const DEFAULT_ENDGAME_THRESHOLD: usize = 20;
+
+/// Optimal play from a position to the end of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndgameSolution {
+    /// In turn order, with every player playing for their own score
+    pub scores: Vec<(Occupancy, i32)>,
+    /// The turns to the end of the game, forced passes included
+    pub principal_variation: Vec<Turn>,
+    /// Positions searched
+    pub nodes: u64,
+}
+
+/// How many legal moves the players still in have between them
+///
+/// A rough measure of how big the rest of the game is, cheap enough to check every turn.
+pub fn endgame_complexity<const D: usize>(game: &GameState<D>) -> usize {
+    game.turn_order()
+        .iter()
+        .filter(|occupancy| !game.has_passed(**occupancy))
+        .map(|occupancy| game.board().legal_moves(*occupancy).len())
+        .sum()
+}
+
+/// Searches every line to the end of the game
+///
+/// Each player maximizes their own final score, taking the bigger piece on ties. Passing
+/// never raises a player's own score, so only placements are searched. Check
+/// [`endgame_complexity`] first, the search is exponential in it.
+pub fn solve_endgame<const D: usize>(game: &GameState<D>, scoring: Scoring) -> EndgameSolution {
+    let Ok(solution) = solve_until(game, scoring, None) else {
+        unreachable!("there is no deadline to run past")
+    };
+    solution
+}
+
+/// The solver's first move once the game is simple enough, None otherwise
+///
+/// A bot searching for `time_limit` from `start` gives the solver half of it at most,
+/// so the bot still has time for its own search if the solver runs out. That returns
+/// None too.
+pub(super) fn endgame_move<const D: usize>(
+    game: &GameState<D>,
+    threshold: Option<usize>,
+    start: Instant,
+    time_limit: Option<Duration>,
+) -> Option<Move> {
+    let threshold = threshold?;
+    if game.is_over() || endgame_complexity(game) > threshold {
+        return None;
+    }
+    let deadline = time_limit.map(|limit| start + limit / 2);
+    let solution = solve_until(game, game.variant().scoring(), deadline).ok()?;
+    match solution.principal_variation.first()? {
+        Turn::Placed { mv, .. } => Some(*mv),
+        Turn::Passed { .. } => None,
+    }
+}
+
+fn solve_until<const D: usize>(
+    game: &GameState<D>,
+    scoring: Scoring,
+    deadline: Option<Instant>,
+) -> Result<EndgameSolution, OutOfTime> {
+    let mut solver = Solver {
+        scoring,
+        deadline,
+        solved: HashMap::new(),
+        nodes: 0,
+    };
+    let mut game = game.clone();
+    let start = game.history().len();
+    let scores = solver.solve(&mut game)?;
+
+    while let Some(occupancy) = game.current_player() {
+        let (mv, _) = &solver.solved[&solver.key(&game)];
+        let mv = mv.expect("players to move have a best move");
+        game.play(occupancy, mv).expect("solved moves are legal");
+    }
+
+    Ok(EndgameSolution {
+        scores: game.turn_order().iter().copied().zip(scores).collect(),
+        principal_variation: game.history()[start..].to_vec(),
+        nodes: solver.nodes,
+    })
+}
+
+struct Solver {
+    scoring: Scoring,
+    deadline: Option<Instant>,
+    /// The best move, None once the game is over, and the scores it leads to
+    solved: HashMap<(u64, u8), (Option<Move>, Vec<i32>)>,
+    nodes: u64,
+}
+
+impl Solver {
+    /// Final scores in turn order
+    fn solve<const D: usize>(&mut self, game: &mut GameState<D>) -> Result<Vec<i32>, OutOfTime> {
+        self.nodes += 1;
+        check_clock(self.nodes, self.deadline)?;
+        let key = self.key(game);
+        if let Some((_, scores)) = self.solved.get(&key) {
+            return Ok(scores.clone());
+        }
+
+        let Some(occupancy) = game.current_player() else {
+            let scores: Vec<_> = game
+                .turn_order()
+                .iter()
+                .map(|o| game.score(*o, self.scoring))
+                .collect();
+            self.solved.insert(key, (None, scores.clone()));
+            return Ok(scores);
+        };
+
+        let me = game
+            .turn_order()
+            .iter()
+            .position(|o| *o == occupancy)
+            .expect("players are in the turn order");
+        let ceiling = self.best_possible(game, occupancy);
+        let mut moves = game.board().legal_moves(occupancy);
+        moves.sort_by_key(|mv| Reverse(mv.piece.square_count()));
+
+        let mut best: Option<(Move, Vec<i32>)> = None;
+        for mv in moves {
+            game.play(occupancy, mv).expect("legal moves can be played");
+            let scores = self.solve(game);
+            game.undo();
+            let scores = scores?;
+
+            if best.as_ref().is_none_or(|(_, best)| scores[me] > best[me]) {
+                best = Some((mv, scores));
+            }
+            if best.as_ref().is_some_and(|(_, best)| best[me] == ceiling) {
+                break;
+            }
+        }
+
+        let (mv, scores) = best.expect("players to move have a legal move");
+        self.solved.insert(key, (Some(mv), scores.clone()));
+        Ok(scores)
+    }
+
+    /// The score for placing every piece left, nothing does better
+    fn best_possible<const D: usize>(&self, game: &GameState<D>, occupancy: Occupancy) -> i32 {
+        match self.scoring {
+            Scoring::Basic => 0,
+            Scoring::Advanced if game.board().inventory(occupancy).contains(&Piece::One) => {
+                ALL_PLACED_BONUS + MONOMINO_LAST_BONUS
+            }
+            Scoring::Advanced => ALL_PLACED_BONUS,
+        }
+    }
+
+    /// The position's hash, plus who placed the monomino last under advanced scoring,
+    /// which the position alone does not tell
+    fn key<const D: usize>(&self, game: &GameState<D>) -> (u64, u8) {
+        let mut monomino_last = 0;
+        if self.scoring == Scoring::Advanced {
+            for (i, occupancy) in game.turn_order().iter().enumerate() {
+                if game.score(*occupancy, self.scoring) == ALL_PLACED_BONUS + MONOMINO_LAST_BONUS {
+                    monomino_last |= 1 << i;
+                }
+            }
+        }
+        (game.zobrist_hash(), monomino_last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// Plays random moves until the rest of the game is small enough to brute force
+    fn small_endgame(seed: u64, complexity: usize) -> GameState<DUO_DIM> {
+        let mut game = GameState::with_variant(Variant::Duo);
+        play_randomly(&mut game, seed, |game| {
+            endgame_complexity(game) <= complexity
+        });
+        game
+    }
+
+    /// Every line to the end, with no table and no cutoffs, breaking ties like the solver
+    fn brute_force(game: &mut GameState<DUO_DIM>, scoring: Scoring) -> Vec<i32> {
+        let Some(occupancy) = game.current_player() else {
+            return game
+                .turn_order()
+                .iter()
+                .map(|o| game.score(*o, scoring))
+                .collect();
+        };
+        let me = turn_index(game, occupancy);
+        let mut moves = game.board().legal_moves(occupancy);
+        moves.sort_by_key(|mv| Reverse(mv.piece.square_count()));
+
+        let mut best: Option<Vec<i32>> = None;
+        for mv in moves {
+            game.play(occupancy, mv).unwrap();
+            let scores = brute_force(game, scoring);
+            game.undo();
+            if best.as_ref().is_none_or(|best| scores[me] > best[me]) {
+                best = Some(scores);
+            }
+        }
+        best.unwrap()
+    }
+
+    fn turn_index(game: &GameState<DUO_DIM>, occupancy: Occupancy) -> usize {
+        game.turn_order()
+            .iter()
+            .position(|o| *o == occupancy)
+            .unwrap()
+    }
+
+    #[test]
+    fn solver_matches_brute_force() {
+        for seed in 1..9 {
+            let game = small_endgame(seed, 10);
+            assert!(!game.is_over());
+            for scoring in [Scoring::Basic, Scoring::Advanced] {
+                let solution = solve_endgame(&game, scoring);
+                let scores: Vec<_> = solution.scores.iter().map(|(_, score)| *score).collect();
+                assert_eq!(scores, brute_force(&mut game.clone(), scoring));
+
+                // Playing out the principal variation gets the scores it promises
+                let mut played = game.clone();
+                for turn in &solution.principal_variation {
+                    match *turn {
+                        Turn::Placed { occupancy, mv } => played.play(occupancy, mv).unwrap(),
+                        Turn::Passed { .. } => {}
+                    }
+                }
+                assert!(played.is_over());
+                for (occupancy, score) in &solution.scores {
+                    assert_eq!(played.score(*occupancy, scoring), *score);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn endgame_move_gives_up_past_the_deadline() {
+        let game = small_endgame(1, 60);
+        let now = Instant::now();
+        let no_time = Some(Duration::ZERO);
+        assert_eq!(endgame_move(&game, Some(usize::MAX), now, no_time), None);
+        assert_eq!(endgame_move(&game, Some(0), now, None), None);
+        let small = small_endgame(1, 10);
+        assert!(endgame_move(&small, Some(10), now, None).is_some());
+        // Half of a long budget is plenty for a small endgame
+        let minute = Some(Duration::from_secs(60));
+        assert!(endgame_move(&small, Some(10), now, minute).is_some());
+    }
+}
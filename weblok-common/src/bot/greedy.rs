@@ -0,0 +1,201 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::*;
+
+/// How much the greedy bot cares about each feature of a placement
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GreedyWeights {
+    /// Per square of the piece
+    pub piece_size: f32,
+    /// Per corner anchor the placement opens up for the player
+    pub own_anchors: f32,
+    /// Per opponent anchor the piece covers
+    pub blocked_anchors: f32,
+    /// Per square between the middle of the piece and the center of the board
+    /// Negative to head for the center
+    pub center_distance: f32,
+}
+
+impl GreedyWeights {
+    pub const BALANCED: GreedyWeights = GreedyWeights {
+        piece_size: 1.,
+        own_anchors: 0.5,
+        blocked_anchors: 0.7,
+        center_distance: -0.3,
+    };
+
+    /// Goes out of its way to sit on other players' corners
+    pub const BLOCKER: GreedyWeights = GreedyWeights {
+        blocked_anchors: 2.,
+        ..GreedyWeights::BALANCED
+    };
+
+    /// Keeps as many ways forward open as it can
+    pub const EXPANDER: GreedyWeights = GreedyWeights {
+        own_anchors: 1.5,
+        ..GreedyWeights::BALANCED
+    };
+}
+
+impl Default for GreedyWeights {
+    fn default() -> Self {
+        GreedyWeights::BALANCED
+    }
+}
+
+/// Plays the legal move with the best weighted score, ties are broken at random
+#[derive(Clone, Debug)]
+pub struct GreedyBot {
+    pub weights: GreedyWeights,
+    rng: StdRng,
+}
+
+impl GreedyBot {
+    pub fn new(weights: GreedyWeights, seed: u64) -> GreedyBot {
+        GreedyBot {
+            weights,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<const D: usize> Bot<D> for GreedyBot {
+    fn choose_move(&mut self, game: &GameState<D>) -> Option<Move> {
+        let occupancy = game.current_player()?;
+        let evaluator = Evaluator::new(game, occupancy);
+
+        let mut best = vec![];
+        let mut best_score = f32::NEG_INFINITY;
+        for mv in game.board().legal_moves(occupancy) {
+            let score = evaluator.score(&self.weights, mv);
+            if score > best_score {
+                best.clear();
+                best_score = score;
+            }
+            if score == best_score {
+                best.push(mv);
+            }
+        }
+        best.choose(&mut self.rng).copied()
+    }
+}
+
+/// What every placement of a position is compared against
+struct Evaluator<const D: usize> {
+    occupied: Bitboard<D>,
+    own_side_neighbors: Bitboard<D>,
+    own_anchors: Bitboard<D>,
+    opponent_anchors: Vec<Bitboard<D>>,
+}
+
+impl<const D: usize> Evaluator<D> {
+    fn new(game: &GameState<D>, occupancy: Occupancy) -> Evaluator<D> {
+        let board = game.board();
+        Evaluator {
+            occupied: *board.occupied(),
+            own_side_neighbors: board.bitboard(occupancy).side_neighbors(),
+            own_anchors: board.anchors(occupancy),
+            opponent_anchors: game
+                .turn_order()
+                .iter()
+                .filter(|o| **o != occupancy && !game.has_passed(**o))
+                .map(|o| board.anchors(*o))
+                .collect(),
+        }
+    }
+
+    fn score(&self, weights: &GreedyWeights, mv: Move) -> f32 {
+        let mask = mv.piece.mask(mv.orientation);
+        let mut piece = Bitboard::new();
+        mask.place_on(&mut piece, mv.col, mv.row);
+
+        let taken = self.occupied | piece;
+        let side_neighbors = self.own_side_neighbors | mask.side_neighbors_at(mv.col, mv.row);
+        let new_anchors = mask.corner_neighbors_at(mv.col, mv.row)
+            & !taken
+            & !side_neighbors
+            & !self.own_anchors;
+        let blocked: u32 = self
+            .opponent_anchors
+            .iter()
+            .map(|anchors| (*anchors & piece).count())
+            .sum();
+
+        let center = (D as f32 - 1.) / 2.;
+        let middle_x = mv.col as f32 + (mask.width as f32 - 1.) / 2.;
+        let middle_y = mv.row as f32 + (mask.height as f32 - 1.) / 2.;
+        let center_distance = (middle_x - center).hypot(middle_y - center);
+
+        weights.piece_size * mv.piece.square_count() as f32
+            + weights.own_anchors * new_anchors.count() as f32
+            + weights.blocked_anchors * blocked as f32
+            + weights.center_distance * center_distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTHING: GreedyWeights = GreedyWeights {
+        piece_size: 0.,
+        own_anchors: 0.,
+        blocked_anchors: 0.,
+        center_distance: 0.,
+    };
+
+    const SIZE_ONLY: GreedyWeights = GreedyWeights {
+        piece_size: 1.,
+        ..NOTHING
+    };
+
+    #[test]
+    fn size_weight_picks_the_biggest_piece() {
+        let game = GameState::new();
+        let mv = GreedyBot::new(SIZE_ONLY, 0).choose_move(&game).unwrap();
+        assert_eq!(mv.piece.square_count(), 5);
+
+        let tiny = GreedyWeights {
+            piece_size: -1.,
+            ..NOTHING
+        };
+        let mv = GreedyBot::new(tiny, 0).choose_move(&game).unwrap();
+        assert_eq!(mv.piece, Piece::One);
+    }
+
+    #[test]
+    fn raising_the_block_weight_trades_size_for_blocking() {
+        let blocks_only = GreedyWeights {
+            blocked_anchors: 1.,
+            ..NOTHING
+        };
+        let blocker = GreedyWeights {
+            blocked_anchors: 10.,
+            ..SIZE_ONLY
+        };
+
+        // The first position where the biggest piece doesn't block as much as it could
+        let mut game = GameState::<DUO_DIM>::with_variant(Variant::Duo);
+        let mut random = RandomBot::new(0);
+        loop {
+            let occupancy = game.current_player().expect("some position has a choice");
+            let evaluator = Evaluator::new(&game, occupancy);
+            let blocked = |mv: Move| evaluator.score(&blocks_only, mv);
+            let most_blocked = game
+                .board()
+                .legal_moves(occupancy)
+                .into_iter()
+                .map(blocked)
+                .fold(0., f32::max);
+
+            let big = GreedyBot::new(SIZE_ONLY, 0).choose_move(&game).unwrap();
+            if blocked(big) < most_blocked {
+                let block = GreedyBot::new(blocker, 0).choose_move(&game).unwrap();
+                assert_eq!(blocked(block), most_blocked);
+                assert!(blocked(block) > blocked(big));
+                return;
+            }
+            play_turn(&mut random, &mut game).unwrap();
+        }
+    }
+}
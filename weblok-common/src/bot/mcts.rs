@@ -0,0 +1,436 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+use super::*;
+
+/// When to stop searching
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    /// Same seed, same moves
+    Playouts(u32),
+    /// Depends on the machine, so not reproducible
+    Time(Duration),
+}
+
+/// How a game is finished from a new node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rollout {
+    Random,
+    Greedy(GreedyWeights),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MctsConfig {
+    pub budget: Budget,
+    /// The UCT exploration constant, higher tries more moves
+    pub exploration: f32,
+    pub rollout: Rollout,
+    /// Independent trees searched side by side, with the playouts split between them
+    pub threads: usize,
+    /// None to never use the endgame solver, see [`DEFAULT_ENDGAME_THRESHOLD`]
+    pub endgame_threshold: Option<usize>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            budget: Budget::Playouts(1000),
+            exploration: std::f32::consts::SQRT_2,
+            rollout: Rollout::Random,
+            threads: 1,
+            endgame_threshold: Some(DEFAULT_ENDGAME_THRESHOLD),
+        }
+    }
+}
+
+/// How a move at the root did during the last search
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CandidateStats {
+    pub mv: Move,
+    pub visits: u32,
+    /// Share of the playouts through the move won by the player to move, ties split
+    pub win_rate: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchStats {
+    /// Across every tree
+    pub playouts: u32,
+    pub elapsed: Duration,
+    /// Most visited first
+    pub candidates: Vec<CandidateStats>,
+}
+
+/// Monte Carlo tree search with UCT selection
+///
+/// Every node keeps the rewards of the player who moved into it, so each player picks
+/// the moves that are best for themselves, however many players there are. With more
+/// than one thread, each searches its own tree and their root statistics are summed.
+#[derive(Clone, Debug)]
+pub struct MctsBot {
+    pub config: MctsConfig,
+    rng: StdRng,
+    stats: Option<SearchStats>,
+}
+
+impl MctsBot {
+    pub fn new(config: MctsConfig, seed: u64) -> MctsBot {
+        MctsBot {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            stats: None,
+        }
+    }
+
+    /// From the last move chosen
+    pub fn stats(&self) -> Option<&SearchStats> {
+        self.stats.as_ref()
+    }
+
+    fn search<const D: usize>(
+        &mut self,
+        game: &GameState<D>,
+        occupancy: Occupancy,
+        start: Instant,
+    ) {
+        let threads = self.config.threads.max(1);
+        let mut trees: Vec<_> = (0..threads)
+            .map(|i| {
+                let budget = match self.config.budget {
+                    Budget::Playouts(playouts) => {
+                        let share = playouts / threads as u32;
+                        let extra = (i < playouts as usize % threads) as u32;
+                        Budget::Playouts(share + extra)
+                    }
+                    time => time,
+                };
+                Tree::new(self.config, budget, self.rng.gen())
+            })
+            .collect();
+
+        let results = search_in_parallel(&mut trees, |tree| tree.search(game, occupancy, start));
+
+        // Summed in tree order, so the candidates come out the same every time
+        let mut playouts = 0;
+        let mut totals: Vec<(Move, u32, f32)> = vec![];
+        for (tree_playouts, children) in results {
+            playouts += tree_playouts;
+            for (mv, visits, reward) in children {
+                match totals.iter_mut().find(|(seen, ..)| *seen == mv) {
+                    Some(total) => {
+                        total.1 += visits;
+                        total.2 += reward;
+                    }
+                    None => totals.push((mv, visits, reward)),
+                }
+            }
+        }
+
+        let mut candidates: Vec<_> = totals
+            .into_iter()
+            .map(|(mv, visits, reward)| CandidateStats {
+                mv,
+                visits,
+                win_rate: reward / visits as f32,
+            })
+            .collect();
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.visits));
+
+        self.stats = Some(SearchStats {
+            playouts,
+            elapsed: start.elapsed(),
+            candidates,
+        });
+    }
+}
+
+impl<const D: usize> Bot<D> for MctsBot {
+    /// The most visited move at the root
+    fn choose_move(&mut self, game: &GameState<D>) -> Option<Move> {
+        let occupancy = game.current_player()?;
+        let start = Instant::now();
+        let time_limit = match self.config.budget {
+            Budget::Playouts(_) => None,
+            Budget::Time(budget) => Some(budget),
+        };
+        if let Some(mv) = endgame_move(game, self.config.endgame_threshold, start, time_limit) {
+            self.stats = None;
+            return Some(mv);
+        }
+        self.search(game, occupancy, start);
+        let best = self
+            .stats()?
+            .candidates
+            .first()
+            .map(|candidate| candidate.mv);
+        // A budget too small for a single playout leaves no candidates, so play the
+        // biggest piece like MinimaxBot instead of passing
+        best.or_else(|| {
+            game.board()
+                .legal_moves(occupancy)
+                .into_iter()
+                .min_by_key(|mv| std::cmp::Reverse(mv.piece.square_count()))
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum RolloutBot {
+    Random(RandomBot),
+    Greedy(GreedyBot),
+}
+
+struct Node {
+    /// The move that led here and who played it, None at the root
+    mv: Option<(Occupancy, Move)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    /// Summed over the playouts through this node, for the player who moved into it
+    reward: f32,
+}
+
+impl Node {
+    fn new(mv: Option<(Occupancy, Move)>, parent: Option<usize>, untried: Vec<Move>) -> Node {
+        Node {
+            mv,
+            parent,
+            children: vec![],
+            untried,
+            visits: 0,
+            reward: 0.,
+        }
+    }
+}
+
+/// One thread's search
+struct Tree {
+    exploration: f32,
+    budget: Budget,
+    rng: StdRng,
+    rollout: RolloutBot,
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(config: MctsConfig, budget: Budget, seed: u64) -> Tree {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rollout = match config.rollout {
+            Rollout::Random => RolloutBot::Random(RandomBot::new(rng.gen())),
+            Rollout::Greedy(weights) => RolloutBot::Greedy(GreedyBot::new(weights, rng.gen())),
+        };
+        Tree {
+            exploration: config.exploration,
+            budget,
+            rng,
+            rollout,
+            nodes: vec![],
+        }
+    }
+
+    /// The playouts run, and the visits and rewards of each move at the root
+    fn search<const D: usize>(
+        &mut self,
+        game: &GameState<D>,
+        occupancy: Occupancy,
+        start: Instant,
+    ) -> (u32, Vec<(Move, u32, f32)>) {
+        self.nodes = vec![Node::new(None, None, game.board().legal_moves(occupancy))];
+        let mut playouts = 0;
+
+        while !self.is_out_of_budget(playouts, start) {
+            let mut state = game.clone();
+            let mut node = 0;
+
+            while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+                node = self.select_child(node);
+                let (player, mv) = self.nodes[node].mv.expect("only the root has no move");
+                state.play(player, mv).expect("tree moves are legal");
+            }
+
+            if let Some(player) = state.current_player() {
+                let untried = &mut self.nodes[node].untried;
+                let mv = untried.swap_remove(self.rng.gen_range(0..untried.len()));
+                state.play(player, mv).expect("untried moves are legal");
+
+                let untried = match state.current_player() {
+                    Some(next) => state.board().legal_moves(next),
+                    None => vec![],
+                };
+                self.nodes
+                    .push(Node::new(Some((player, mv)), Some(node), untried));
+                let child = self.nodes.len() - 1;
+                self.nodes[node].children.push(child);
+                node = child;
+            }
+
+            self.finish(&mut state);
+            let result = state.standings(state.variant().scoring());
+
+            let mut current = Some(node);
+            while let Some(i) = current {
+                let node = &mut self.nodes[i];
+                node.visits += 1;
+                if let Some((player, _)) = node.mv {
+                    if result.winners.contains(&player) {
+                        node.reward += 1. / result.winners.len() as f32;
+                    }
+                }
+                current = node.parent;
+            }
+            playouts += 1;
+        }
+
+        let children = self.nodes[0]
+            .children
+            .iter()
+            .map(|child| {
+                let node = &self.nodes[*child];
+                let (_, mv) = node.mv.expect("children have moves");
+                (mv, node.visits, node.reward)
+            })
+            .collect();
+        (playouts, children)
+    }
+
+    fn is_out_of_budget(&self, playouts: u32, start: Instant) -> bool {
+        match self.budget {
+            Budget::Playouts(budget) => playouts >= budget,
+            Budget::Time(budget) => start.elapsed() >= budget,
+        }
+    }
+
+    /// The child with the best upper confidence bound for the player choosing
+    fn select_child(&self, node: usize) -> usize {
+        let log_visits = (self.nodes[node].visits as f32).ln();
+        let uct = |child: usize| {
+            let Node { visits, reward, .. } = self.nodes[child];
+            let visits = visits as f32;
+            reward / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+
+        let mut children = self.nodes[node].children.iter().copied();
+        let first = children.next().expect("only called with children");
+        children
+            .fold((first, uct(first)), |best, child| {
+                let score = uct(child);
+                if score > best.1 {
+                    (child, score)
+                } else {
+                    best
+                }
+            })
+            .0
+    }
+
+    /// Plays the rollout policy until the game is over
+    fn finish<const D: usize>(&mut self, state: &mut GameState<D>) {
+        let bot: &mut dyn Bot<D> = match &mut self.rollout {
+            RolloutBot::Random(bot) => bot,
+            RolloutBot::Greedy(bot) => bot,
+        };
+        while play_turn(bot, state).is_ok() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playouts(playouts: u32) -> MctsConfig {
+        MctsConfig {
+            budget: Budget::Playouts(playouts),
+            ..MctsConfig::default()
+        }
+    }
+
+    /// A few moves into a Duo game
+    fn opening() -> GameState<DUO_DIM> {
+        let mut game = GameState::with_variant(Variant::Duo);
+        let mut random = RandomBot::new(0);
+        for _ in 0..4 {
+            play_turn(&mut random, &mut game).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn every_playout_visits_one_root_move() {
+        let game = opening();
+        let mut bot = MctsBot::new(playouts(300), 0);
+        let mv = bot.choose_move(&game).unwrap();
+
+        let stats = bot.stats().unwrap();
+        assert_eq!(stats.playouts, 300);
+        let visits: u32 = stats.candidates.iter().map(|c| c.visits).sum();
+        assert_eq!(visits, 300);
+        assert_eq!(stats.candidates[0].mv, mv);
+        let legal = game.board().legal_moves(game.current_player().unwrap());
+        assert!(stats.candidates.iter().all(|c| legal.contains(&c.mv)));
+    }
+
+    #[test]
+    fn same_seed_same_search() {
+        let game = opening();
+        let search = |seed| {
+            let mut bot = MctsBot::new(playouts(200), seed);
+            let mv = bot.choose_move(&game);
+            let stats = bot.stats().unwrap();
+            (mv, stats.playouts, stats.candidates.clone())
+        };
+        assert_eq!(search(7), search(7));
+    }
+
+    #[test]
+    fn a_single_legal_move_is_played() {
+        let pieces = PieceSet::standard().with_handicap(Occupancy::Violet, vec![Piece::One]);
+        let game =
+            GameState::<DUO_DIM>::with_piece_set(Variant::Duo, Variant::Duo.turn_order(), &pieces);
+        let only = game.board().legal_moves(Occupancy::Violet);
+        assert_eq!(only.len(), 1);
+
+        let mut bot = MctsBot::new(playouts(50), 0);
+        assert_eq!(bot.choose_move(&game), Some(only[0]));
+        assert_eq!(bot.stats().unwrap().candidates.len(), 1);
+    }
+
+    #[test]
+    fn threads_split_the_playouts_reproducibly() {
+        let game = opening();
+        let search = || {
+            let mut bot = MctsBot::new(
+                MctsConfig {
+                    threads: 3,
+                    ..playouts(301)
+                },
+                7,
+            );
+            let mv = bot.choose_move(&game);
+            let stats = bot.stats().unwrap();
+            (mv, stats.playouts, stats.candidates.clone())
+        };
+        let first = search();
+        assert_eq!(first.1, 301);
+        assert_eq!(first.2.iter().map(|c| c.visits).sum::<u32>(), 301);
+        assert_eq!(search(), first);
+    }
+
+    #[test]
+    fn no_budget_still_plays_a_legal_move() {
+        let game = opening();
+        let legal = game.board().legal_moves(game.current_player().unwrap());
+        for budget in [
+            Budget::Playouts(0),
+            Budget::Playouts(1),
+            Budget::Time(Duration::ZERO),
+        ] {
+            let config = MctsConfig {
+                budget,
+                ..MctsConfig::default()
+            };
+            let mv = MctsBot::new(config, 0).choose_move(&game).unwrap();
+            assert!(legal.contains(&mv));
+        }
+    }
+}
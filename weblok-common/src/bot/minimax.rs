@@ -0,0 +1,548 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::*;
+
+/// How the other players are assumed to play
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchKind {
+    /// Everyone plays for themselves
+    MaxN,
+    /// Everyone else plays against the bot, which makes it a two player game
+    Paranoid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimaxConfig {
+    pub kind: SearchKind,
+    /// In turns, counting every player's
+    pub max_depth: u32,
+    /// Stops deepening once out of time, None to always reach max_depth
+    /// Only searches without a time limit are reproducible
+    pub time_limit: Option<Duration>,
+    /// What an anchor is worth in the evaluation, next to a placed square
+    pub anchor_weight: f32,
+    /// Reuses values found for the same position through another move order
+    /// Turning it off only slows the search down, best moves are still kept for ordering
+    pub use_table: bool,
+    /// Threads splitting the moves at the root between them
+    pub threads: usize,
+    /// None to never use the endgame solver, see [`DEFAULT_ENDGAME_THRESHOLD`]
+    pub endgame_threshold: Option<usize>,
+}
+
+impl Default for MinimaxConfig {
+    fn default() -> Self {
+        MinimaxConfig {
+            kind: SearchKind::Paranoid,
+            max_depth: 2,
+            time_limit: None,
+            anchor_weight: 0.5,
+            use_table: true,
+            threads: 1,
+            endgame_threshold: Some(DEFAULT_ENDGAME_THRESHOLD),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinimaxStats {
+    /// Of the deepest iteration completed
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// The best move after each completed iteration, shallowest first
+    pub best_moves: Vec<Move>,
+    /// The bot's expected share of the evaluation, from 0 to 1
+    pub value: f32,
+}
+
+/// Depth-limited search with alpha-beta pruning and iterative deepening
+///
+/// Positions are scored as each player's share of the placed squares and anchors, so
+/// the scores add up to 1. Max-n uses that bound for shallow pruning, paranoid search
+/// only looks at the bot's own share. Moves are tried biggest piece first, after the
+/// best move from a shallower search kept in a transposition table.
+#[derive(Clone, Debug)]
+pub struct MinimaxBot {
+    pub config: MinimaxConfig,
+    stats: Option<MinimaxStats>,
+}
+
+impl MinimaxBot {
+    pub fn new(config: MinimaxConfig) -> MinimaxBot {
+        MinimaxBot {
+            config,
+            stats: None,
+        }
+    }
+
+    /// From the last move chosen
+    pub fn stats(&self) -> Option<&MinimaxStats> {
+        self.stats.as_ref()
+    }
+}
+
+impl<const D: usize> Bot<D> for MinimaxBot {
+    /// The best move of the deepest search finished in time
+    fn choose_move(&mut self, game: &GameState<D>) -> Option<Move> {
+        let occupancy = game.current_player()?;
+        let start = Instant::now();
+        let deadline = self.config.time_limit.map(|limit| start + limit);
+        let threshold = self.config.endgame_threshold;
+        if let Some(mv) = endgame_move(game, threshold, start, self.config.time_limit) {
+            self.stats = None;
+            return Some(mv);
+        }
+        let threads = self.config.threads.max(1);
+        let mut searches: Vec<_> = (0..threads)
+            .map(|first| (first, Search::new(self.config, deadline), game.clone()))
+            .collect();
+
+        let mut moves = game.board().legal_moves(occupancy);
+        moves.sort_by_key(|mv| Reverse(mv.piece.square_count()));
+        let mut stats = MinimaxStats {
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            best_moves: vec![],
+            value: searches[0].1.evaluate(game)[turn_index(game, occupancy)],
+        };
+
+        for depth in 1..=self.config.max_depth {
+            let results = search_in_parallel(&mut searches, |(first, search, game)| {
+                search.search_root(game, depth, &moves, *first, threads)
+            });
+            let Ok(results) = results.into_iter().collect::<Result<Vec<_>, _>>() else {
+                break;
+            };
+
+            // Ties go to the move ordered first, whichever thread had it
+            let (best, value) = results
+                .into_iter()
+                .flatten()
+                .reduce(|best, result| {
+                    if result.1 > best.1 || (result.1 == best.1 && result.0 < best.0) {
+                        result
+                    } else {
+                        best
+                    }
+                })
+                .expect("players to move have a legal move");
+            moves[..=best].rotate_right(1);
+            stats.depth = depth;
+            stats.value = value;
+            stats.best_moves.push(moves[0]);
+        }
+
+        stats.nodes = searches.iter().map(|(_, search, _)| search.nodes).sum();
+        stats.elapsed = start.elapsed();
+        self.stats = Some(stats);
+        // Biggest piece first if out of time before the first iteration finished
+        Some(moves[0])
+    }
+}
+
+/// Whether a stored value is exact or was cut off by pruning
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+struct Entry {
+    depth: u32,
+    best: Move,
+    /// Every player's in turn order for max-n, only the bot's for paranoid search
+    values: Vec<f32>,
+    bound: Bound,
+}
+
+struct Search {
+    config: MinimaxConfig,
+    deadline: Option<Instant>,
+    nodes: u64,
+    /// By Zobrist hash
+    table: HashMap<u64, Entry>,
+}
+
+impl Search {
+    fn new(config: MinimaxConfig, deadline: Option<Instant>) -> Search {
+        Search {
+            config,
+            deadline,
+            nodes: 0,
+            table: HashMap::new(),
+        }
+    }
+
+    fn visit(&mut self) -> Result<(), OutOfTime> {
+        self.nodes += 1;
+        check_clock(self.nodes, self.deadline)
+    }
+
+    /// Each player's share, in turn order
+    fn evaluate<const D: usize>(&self, game: &GameState<D>) -> Vec<f32> {
+        let board = game.board();
+        let weights: Vec<f32> = game
+            .turn_order()
+            .iter()
+            .map(|occupancy| {
+                let placed = board.bitboard(*occupancy).count();
+                let anchors = if game.has_passed(*occupancy) {
+                    0
+                } else {
+                    board.anchors(*occupancy).count()
+                };
+                // Keeps the total above 0 before anyone has placed
+                1. + placed as f32 + self.config.anchor_weight * anchors as f32
+            })
+            .collect();
+        let total: f32 = weights.iter().sum();
+        weights.iter().map(|weight| weight / total).collect()
+    }
+
+    /// Biggest pieces first, after the stored best move
+    fn ordered_moves<const D: usize>(
+        &self,
+        game: &GameState<D>,
+        occupancy: Occupancy,
+        hash: u64,
+    ) -> Vec<Move> {
+        let mut moves = game.board().legal_moves(occupancy);
+        moves.sort_by_key(|mv| Reverse(mv.piece.square_count()));
+        if let Some(entry) = self.table.get(&hash) {
+            if let Some(i) = moves.iter().position(|mv| *mv == entry.best) {
+                moves[..=i].rotate_right(1);
+            }
+        }
+        moves
+    }
+
+    /// The stored entry, if its values can stand in for a search
+    fn reusable(&self, hash: u64) -> Option<&Entry> {
+        self.table.get(&hash).filter(|_| self.config.use_table)
+    }
+
+    fn store(&mut self, hash: u64, depth: u32, best: Move, values: Vec<f32>, bound: Bound) {
+        let entry = Entry {
+            depth,
+            best,
+            values,
+            bound,
+        };
+        self.table.insert(hash, entry);
+    }
+
+    /// The best of every `step`th root move from `first`, by index and the bot's value
+    ///
+    /// Threads take turns picking root moves, so the ones each thread gets only depend
+    /// on how many threads there are.
+    fn search_root<const D: usize>(
+        &mut self,
+        game: &mut GameState<D>,
+        depth: u32,
+        moves: &[Move],
+        first: usize,
+        step: usize,
+    ) -> Result<Option<(usize, f32)>, OutOfTime> {
+        let bot = game
+            .current_player()
+            .expect("the root has a player to move");
+        let me = turn_index(game, bot);
+        let mut best: Option<(usize, f32)> = None;
+        for i in (first..moves.len()).step_by(step) {
+            game.play(bot, moves[i]).expect("legal moves can be played");
+            let alpha = best.map_or(f32::NEG_INFINITY, |(_, value)| value);
+            let value = match self.config.kind {
+                SearchKind::MaxN => {
+                    let bound = match game.current_player() {
+                        Some(next) if next != bot => 1. - alpha,
+                        _ => f32::INFINITY,
+                    };
+                    self.max_n(game, depth - 1, bound).map(|values| values[me])
+                }
+                SearchKind::Paranoid => self.paranoid(game, depth - 1, alpha, f32::INFINITY, bot),
+            };
+            game.undo();
+            let value = value?;
+
+            if best.is_none_or(|(_, best)| value > best) {
+                best = Some((i, value));
+            }
+        }
+        Ok(best)
+    }
+
+    /// Every player's value in turn order
+    ///
+    /// Stops early once the player to move is sure of `bound`, which leaves the player
+    /// before them no more than they already have elsewhere.
+    fn max_n<const D: usize>(
+        &mut self,
+        game: &mut GameState<D>,
+        depth: u32,
+        bound: f32,
+    ) -> Result<Vec<f32>, OutOfTime> {
+        self.visit()?;
+        let Some(occupancy) = game.current_player() else {
+            return Ok(self.evaluate(game));
+        };
+        if depth == 0 {
+            return Ok(self.evaluate(game));
+        }
+
+        let hash = game.zobrist_hash();
+        if let Some(entry) = self.reusable(hash) {
+            if entry.depth >= depth && entry.bound == Bound::Exact {
+                return Ok(entry.values.clone());
+            }
+        }
+
+        let me = turn_index(game, occupancy);
+        let mut best: Option<(Move, Vec<f32>)> = None;
+        let mut exactness = Bound::Exact;
+        for mv in self.ordered_moves(game, occupancy, hash) {
+            game.play(occupancy, mv).expect("legal moves can be played");
+            let child_bound = match (&best, game.current_player()) {
+                (Some((_, values)), Some(next)) if next != occupancy => 1. - values[me],
+                _ => f32::INFINITY,
+            };
+            let values = self.max_n(game, depth - 1, child_bound);
+            game.undo();
+            let values = values?;
+
+            if best.as_ref().is_none_or(|(_, best)| values[me] > best[me]) {
+                best = Some((mv, values));
+            }
+            if best.as_ref().is_some_and(|(_, best)| best[me] >= bound) {
+                exactness = Bound::Lower;
+                break;
+            }
+        }
+
+        let (mv, values) = best.expect("players to move have a legal move");
+        self.store(hash, depth, mv, values.clone(), exactness);
+        Ok(values)
+    }
+
+    /// The bot's value, with every other player minimizing it
+    fn paranoid<const D: usize>(
+        &mut self,
+        game: &mut GameState<D>,
+        depth: u32,
+        mut alpha: f32,
+        mut beta: f32,
+        bot: Occupancy,
+    ) -> Result<f32, OutOfTime> {
+        self.visit()?;
+        let Some(occupancy) = game.current_player() else {
+            return Ok(self.evaluate(game)[turn_index(game, bot)]);
+        };
+        if depth == 0 {
+            return Ok(self.evaluate(game)[turn_index(game, bot)]);
+        }
+
+        let hash = game.zobrist_hash();
+        if let Some(entry) = self.reusable(hash) {
+            if entry.depth >= depth {
+                let value = entry.values[0];
+                match entry.bound {
+                    Bound::Exact => return Ok(value),
+                    Bound::Lower => alpha = alpha.max(value),
+                    Bound::Upper => beta = beta.min(value),
+                }
+                if alpha >= beta {
+                    return Ok(value);
+                }
+            }
+        }
+
+        let (original_alpha, original_beta) = (alpha, beta);
+        let maximizing = occupancy == bot;
+        let mut best: Option<(Move, f32)> = None;
+        for mv in self.ordered_moves(game, occupancy, hash) {
+            game.play(occupancy, mv).expect("legal moves can be played");
+            let value = self.paranoid(game, depth - 1, alpha, beta, bot);
+            game.undo();
+            let value = value?;
+
+            let improves = match best {
+                None => true,
+                Some((_, best)) if maximizing => value > best,
+                Some((_, best)) => value < best,
+            };
+            if improves {
+                best = Some((mv, value));
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let (mv, value) = best.expect("players to move have a legal move");
+        let bound = if value <= original_alpha {
+            Bound::Upper
+        } else if value >= original_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(hash, depth, mv, vec![value], bound);
+        Ok(value)
+    }
+}
+
+fn turn_index<const D: usize>(game: &GameState<D>, occupancy: Occupancy) -> usize {
+    game.turn_order()
+        .iter()
+        .position(|o| *o == occupancy)
+        .expect("players are in the turn order")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every player's value in turn order, without pruning or a table
+    fn plain_max_n<const D: usize>(
+        search: &Search,
+        game: &mut GameState<D>,
+        depth: u32,
+    ) -> Vec<f32> {
+        let Some(occupancy) = game.current_player().filter(|_| depth > 0) else {
+            return search.evaluate(game);
+        };
+        let me = turn_index(game, occupancy);
+        let mut best: Option<Vec<f32>> = None;
+        for mv in game.board().legal_moves(occupancy) {
+            game.play(occupancy, mv).unwrap();
+            let values = plain_max_n(search, game, depth - 1);
+            game.undo();
+            if best.as_ref().is_none_or(|best| values[me] > best[me]) {
+                best = Some(values);
+            }
+        }
+        best.unwrap()
+    }
+
+    /// The bot's value, without pruning or a table
+    fn plain_paranoid<const D: usize>(
+        search: &Search,
+        game: &mut GameState<D>,
+        depth: u32,
+        bot: Occupancy,
+    ) -> f32 {
+        let Some(occupancy) = game.current_player().filter(|_| depth > 0) else {
+            return search.evaluate(game)[turn_index(game, bot)];
+        };
+        let values = game.board().legal_moves(occupancy).into_iter().map(|mv| {
+            game.play(occupancy, mv).unwrap();
+            let value = plain_paranoid(search, game, depth - 1, bot);
+            game.undo();
+            value
+        });
+        let values: Vec<_> = values.collect();
+        let best = if occupancy == bot { f32::max } else { f32::min };
+        values.into_iter().reduce(best).unwrap()
+    }
+
+    /// Duo positions from random games, late enough that a few plies search quickly
+    fn small_positions() -> Vec<GameState<DUO_DIM>> {
+        (0..3)
+            .map(|seed| {
+                let mut game = GameState::with_variant(Variant::Duo);
+                let mut random = RandomBot::new(seed);
+                while game
+                    .current_player()
+                    .is_some_and(|occupancy| game.board().legal_moves(occupancy).len() > 25)
+                {
+                    play_turn(&mut random, &mut game).unwrap();
+                }
+                game
+            })
+            .collect()
+    }
+
+    fn config(kind: SearchKind, max_depth: u32) -> MinimaxConfig {
+        MinimaxConfig {
+            kind,
+            max_depth,
+            ..MinimaxConfig::default()
+        }
+    }
+
+    fn search() -> Search {
+        Search::new(MinimaxConfig::default(), None)
+    }
+
+    #[test]
+    fn pruning_keeps_the_plain_search_value() {
+        for mut game in small_positions() {
+            let Some(occupancy) = game.current_player() else {
+                continue;
+            };
+            let me = turn_index(&game, occupancy);
+            for depth in 1..=3 {
+                let mut bot = MinimaxBot::new(config(SearchKind::MaxN, depth));
+                bot.choose_move(&game).unwrap();
+                let plain = plain_max_n(&search(), &mut game, depth)[me];
+                assert!((bot.stats().unwrap().value - plain).abs() < 1e-6);
+
+                let mut bot = MinimaxBot::new(config(SearchKind::Paranoid, depth));
+                bot.choose_move(&game).unwrap();
+                let plain = plain_paranoid(&search(), &mut game, depth, occupancy);
+                assert_eq!(bot.stats().unwrap().value, plain);
+            }
+        }
+    }
+
+    #[test]
+    fn the_table_only_saves_time() {
+        for game in small_positions() {
+            for kind in [SearchKind::MaxN, SearchKind::Paranoid] {
+                let mut with = MinimaxBot::new(config(kind, 3));
+                let mut without = MinimaxBot::new(MinimaxConfig {
+                    use_table: false,
+                    ..config(kind, 3)
+                });
+                assert_eq!(with.choose_move(&game), without.choose_move(&game));
+                let (with, without) = (with.stats().unwrap(), without.stats().unwrap());
+                assert_eq!(with.value, without.value);
+                assert_eq!(with.best_moves, without.best_moves);
+                assert!(with.nodes <= without.nodes);
+            }
+        }
+    }
+
+    #[test]
+    fn threads_split_the_same_search() {
+        let game = &small_positions()[0];
+        for kind in [SearchKind::MaxN, SearchKind::Paranoid] {
+            let mut single = MinimaxBot::new(config(kind, 3));
+            let mv = single.choose_move(game);
+            let value = single.stats().unwrap().value;
+            let search = || {
+                let mut bot = MinimaxBot::new(MinimaxConfig {
+                    threads: 3,
+                    ..config(kind, 3)
+                });
+                let mv = bot.choose_move(game);
+                let stats = bot.stats().unwrap().clone();
+                (mv, stats.nodes, stats.best_moves, stats.value)
+            };
+            let first = search();
+            assert_eq!(search(), first);
+            assert_eq!((first.0, first.3), (mv, value));
+        }
+    }
+}
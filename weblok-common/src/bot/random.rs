@@ -0,0 +1,68 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::*;
+
+/// Picks uniformly among the legal moves
+/// The same seed plays the same moves in the same positions
+#[derive(Clone, Debug)]
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<const D: usize> Bot<D> for RandomBot {
+    fn choose_move(&mut self, game: &GameState<D>) -> Option<Move> {
+        let occupancy = game.current_player()?;
+        game.board()
+            .legal_moves(occupancy)
+            .choose(&mut self.rng)
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays the whole game with the bot, checking each move against the legal ones
+    fn self_play<const D: usize>(variant: Variant, seed: u64) -> GameState<D> {
+        let mut game = GameState::with_variant(variant);
+        let mut bot = RandomBot::new(seed);
+        while let Some(occupancy) = game.current_player() {
+            let mv = bot
+                .choose_move(&game)
+                .expect("the current player always has a move");
+            assert!(game.board().legal_moves(occupancy).contains(&mv));
+            game.play(occupancy, mv).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_game() {
+        for seed in 0..3 {
+            let game = self_play::<DUO_DIM>(Variant::Duo, seed);
+            assert_eq!(self_play::<DUO_DIM>(Variant::Duo, seed), game);
+        }
+        let classic = self_play::<CLASSIC_DIM>(Variant::Classic, 0);
+        assert_eq!(self_play::<CLASSIC_DIM>(Variant::Classic, 0), classic);
+        assert_ne!(self_play::<CLASSIC_DIM>(Variant::Classic, 1), classic);
+    }
+
+    #[test]
+    fn nothing_to_play_once_the_game_is_over() {
+        let mut game = self_play::<DUO_DIM>(Variant::Duo, 0);
+        assert_eq!(RandomBot::new(0).choose_move(&game), None);
+        assert_eq!(
+            play_turn(&mut RandomBot::new(0), &mut game),
+            Err(MoveError::GameOver)
+        );
+    }
+}
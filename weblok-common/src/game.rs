@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+use weblok_codegen::*;
+
+mod bitboard;
+pub use self::bitboard::*;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod grid;
+pub use self::grid::*;
+mod history;
+pub use self::history::*;
+mod moves;
+pub use self::moves::*;
+mod notation;
+pub use self::notation::*;
+mod piece_set;
+pub use self::piece_set::*;
+mod replay;
+pub use self::replay::*;
+mod score;
+pub use self::score::*;
+mod snapshot;
+pub use self::snapshot::SnapshotError;
+use self::snapshot::*;
+mod state;
+pub use self::state::*;
+mod variant;
+pub use self::variant::*;
+mod zobrist;
+
+/// Width and height of a classic board
+pub const CLASSIC_DIM: usize = 20;
+/// Width and height of a Blokus Duo board
+pub const DUO_DIM: usize = 14;
+
+const OCCUPANCY_COUNT: usize = 7;
+
+/// A D by D board
+/// Variants are normally played at [`Variant::board_size`], but any size works
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(into = "BoardSnapshot<D>", try_from = "BoardSnapshot<D>")]
+pub struct Board<const D: usize = CLASSIC_DIM> {
+    pub occupancies: Grid<Occupancy, D>,
+    /// The same cells as occupancies, indexed by occupancy
+    bitboards: [Bitboard<D>; OCCUPANCY_COUNT],
+    /// Every non-empty cell
+    occupied: Bitboard<D>,
+    inventories: HashMap<Occupancy, Vec<Piece>>,
+    variant: Variant,
+    /// Placements in the order they were made
+    history: Vec<Placement>,
+    /// Undone placements, the next one to redo last
+    redo: Vec<Placement>,
+    /// Zobrist hash of the cells and inventories, kept up to date by apply and undo
+    hash: u64,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Board {
+        Board::with_variant(Variant::Classic)
+    }
+}
+
+impl<const D: usize> Board<D> {
+    pub fn with_variant(variant: Variant) -> Board<D> {
+        Board::with_piece_set(variant, &PieceSet::standard())
+    }
+
+    pub fn with_piece_set(variant: Variant, pieces: &PieceSet) -> Board<D> {
+        let mut board = Board {
+            occupancies: Grid::new(),
+            bitboards: [Bitboard::new(); OCCUPANCY_COUNT],
+            occupied: Bitboard::new(),
+            inventories: variant
+                .turn_order()
+                .into_iter()
+                .map(|occupancy| (occupancy, pieces.pieces_for(occupancy).to_vec()))
+                .collect(),
+            variant,
+            history: vec![],
+            redo: vec![],
+            hash: 0,
+        };
+        board.hash = board.scan_zobrist_hash();
+        board
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn size(&self) -> usize {
+        D
+    }
+
+    /// The cell the occupancy's first piece has to cover
+    pub fn start_point(&self, occupancy: Occupancy) -> Option<(i8, i8)> {
+        self.variant.start_point(occupancy, D)
+    }
+
+    /// An occupancy has started once its first piece covers its start point
+    pub fn has_started(&self, occupancy: Occupancy) -> bool {
+        self.start_point(occupancy)
+            .is_some_and(|(x, y)| self.bitboard(occupancy).get(x, y))
+    }
+
+    pub fn bitboard(&self, occupancy: Occupancy) -> &Bitboard<D> {
+        &self.bitboards[occupancy as usize]
+    }
+
+    /// Every non-empty cell
+    pub fn occupied(&self) -> &Bitboard<D> {
+        &self.occupied
+    }
+
+    /// Cells the occupancy can never cover: taken, or sharing an edge with its own pieces
+    pub fn forbidden(&self, occupancy: Occupancy) -> Bitboard<D> {
+        self.occupied | self.bitboard(occupancy).side_neighbors()
+    }
+
+    /// Cells touching the occupancy's pieces only at a corner, one of which every
+    /// placement has to cover
+    /// Before its first piece, the occupancy's only anchor is its start point
+    pub fn anchors(&self, occupancy: Occupancy) -> Bitboard<D> {
+        let forbidden = self.forbidden(occupancy);
+        if self.has_started(occupancy) {
+            return self.bitboard(occupancy).corner_neighbors() & !forbidden;
+        }
+
+        let mut anchors = Bitboard::new();
+        if let Some((x, y)) = self.start_point(occupancy) {
+            if !forbidden.get(x, y) {
+                anchors.set(x, y);
+            }
+        }
+        anchors
+    }
+
+    /// The pieces the occupancy has yet to place
+    /// Assumes occupancy is not [`Occupancy::Empty`]
+    pub fn inventory(&self, occupancy: Occupancy) -> &[Piece] {
+        &self.inventories[&occupancy]
+    }
+
+    // Returns false if the placement is invalid
+    pub fn place(
+        &mut self,
+        occupancy: Occupancy,
+        piece: Piece,
+        orientation: Orientation,
+        col: i8,
+        row: i8,
+    ) -> bool {
+        if !self.is_mask_valid(occupancy, piece.mask(orientation), col, row) {
+            return false;
+        }
+        let Some(inventory_index) = self.inventory(occupancy).iter().position(|p| *p == piece)
+        else {
+            return false;
+        };
+
+        self.redo.clear();
+        self.apply(Placement {
+            occupancy,
+            mv: Move::new(piece, orientation, col, row),
+            inventory_index,
+        });
+        true
+    }
+
+    // Returns false if the placement is invalid
+    pub fn is_placement_valid(&self, occupancy: Occupancy, coords: &[(i8, i8)]) -> bool {
+        let forbidden = self.forbidden(occupancy);
+        let anchors = self.anchors(occupancy);
+        let mut has_anchor = false;
+
+        for (x, y) in coords.iter() {
+            if *x as usize >= D || *y as usize >= D || forbidden.get(*x, *y) {
+                return false;
+            }
+            has_anchor |= anchors.get(*x, *y);
+        }
+        has_anchor
+    }
+
+    // Returns false if the placement is invalid
+    fn is_mask_valid(&self, occupancy: Occupancy, mask: &PieceMask, col: i8, row: i8) -> bool {
+        mask.in_bounds::<D>(col, row)
+            && !mask.overlaps(&self.forbidden(occupancy), col, row)
+            && mask.overlaps(&self.anchors(occupancy), col, row)
+    }
+
+    /// Print the valid, invalid, and docking placements for the given occupancy
+    /// Assumes occupancy is not [`Occupancy::Empty`]
+    pub fn print_placements(&self, occupancy: Occupancy) {
+        let forbidden = self.forbidden(occupancy);
+        let anchors = self.anchors(occupancy);
+        for y in 0..D as i8 {
+            for x in 0..D as i8 {
+                if forbidden.get(x, y) {
+                    print!("X");
+                } else if anchors.get(x, y) {
+                    print!("O");
+                } else {
+                    print!("-");
+                }
+            }
+            println!();
+        }
+    }
+
+    pub fn vomit_placements(&self, occupancy: Occupancy, placements: &mut Grid<Validity, D>) {
+        let forbidden = self.forbidden(occupancy);
+        let anchors = self.anchors(occupancy);
+        for y in 0..D as i8 {
+            for x in 0..D as i8 {
+                if forbidden.get(x, y) {
+                    placements.set(x, y, Validity::Invalid);
+                } else if anchors.get(x, y) {
+                    placements.set(x, y, Validity::Anchor);
+                } else {
+                    placements.set(x, y, Validity::Valid);
+                }
+            }
+        }
+    }
+}
+
+/// row and col refer to the upper left corner of the piece bounding box
+/// So the row and col of a piece will relatively changed based on rotation
+/// @return (x,y)
+pub fn coords_for_placement(
+    piece: Piece,
+    orientation: Orientation,
+    col: i8,
+    row: i8,
+) -> Vec<(i8, i8)> {
+    piece
+        .offsets(orientation)
+        .offsets
+        .iter()
+        .map(|(x, y)| (col + x, row + y))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Validity {
+    #[default]
+    Valid,
+    Invalid,
+    Anchor,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Occupancy {
+    #[default]
+    Empty,
+    Green,
+    Red,
+    Blue,
+    Yellow,
+    Violet,
+    Orange,
+}
+
+impl fmt::Display for Occupancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Occupancy::Empty => "·",
+            Occupancy::Green => "G",
+            Occupancy::Red => "R",
+            Occupancy::Blue => "B",
+            Occupancy::Yellow => "Y",
+            Occupancy::Violet => "V",
+            Occupancy::Orange => "O",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    Zero,
+    Ninety,
+    OneEighty,
+    TwoSeventy,
+}
+
+impl Rotation {
+    pub fn next_clockwise(self) -> Rotation {
+        match self {
+            Rotation::Zero => Rotation::TwoSeventy,
+            Rotation::Ninety => Rotation::Zero,
+            Rotation::OneEighty => Rotation::Ninety,
+            Rotation::TwoSeventy => Rotation::OneEighty,
+        }
+    }
+
+    /// The rotation that undoes this one
+    pub fn inverse(self) -> Rotation {
+        match self {
+            Rotation::Zero => Rotation::Zero,
+            Rotation::Ninety => Rotation::TwoSeventy,
+            Rotation::OneEighty => Rotation::OneEighty,
+            Rotation::TwoSeventy => Rotation::Ninety,
+        }
+    }
+}
+
+/// One of the eight dihedral symmetries of a piece
+/// A flipped piece is mirrored left to right before being rotated
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    pub flipped: bool,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 8] = [
+        Orientation::new(Rotation::Zero, false),
+        Orientation::new(Rotation::Ninety, false),
+        Orientation::new(Rotation::OneEighty, false),
+        Orientation::new(Rotation::TwoSeventy, false),
+        Orientation::new(Rotation::Zero, true),
+        Orientation::new(Rotation::Ninety, true),
+        Orientation::new(Rotation::OneEighty, true),
+        Orientation::new(Rotation::TwoSeventy, true),
+    ];
+
+    pub const fn new(rotation: Rotation, flipped: bool) -> Orientation {
+        Orientation { rotation, flipped }
+    }
+
+    pub fn next_clockwise(self) -> Orientation {
+        Orientation {
+            rotation: self.rotation.next_clockwise(),
+            ..self
+        }
+    }
+
+    /// Mirrors the piece left to right as it is currently displayed
+    pub fn flip(self) -> Orientation {
+        Orientation {
+            rotation: self.rotation.inverse(),
+            flipped: !self.flipped,
+        }
+    }
+}
+
+impl From<Rotation> for Orientation {
+    fn from(rotation: Rotation) -> Self {
+        Orientation::new(rotation, false)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PieceOffsets {
+    pub offsets: Vec<(i8, i8)>,
+    pub pivot: (i8, i8),
+}
+
+impl PieceOffsets {
+    pub fn print_repr(&self) {
+        let mut grid = Grid::<char, 5>::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                grid.set(x, y, '-');
+            }
+        }
+        for (x, y) in &self.offsets {
+            grid.set(*x, *y, 'X');
+        }
+        println!("{}", grid);
+    }
+}
+
+/// A piece in all eight orientations, as generated by `piece!`
+/// Offsets start at (0, 0), and every array is in [`Orientation::ALL`] order
+#[derive(Debug)]
+pub struct PieceTable {
+    /// Orientations covering different squares, in [`Orientation::ALL`] order
+    pub orientations: &'static [Orientation],
+    /// The first orientation covering the same squares
+    pub canonical: [Orientation; 8],
+    pub offsets: [&'static [(i8, i8)]; 8],
+    pub pivots: [(i8, i8); 8],
+    pub masks: [PieceMask; 8],
+}
+
+impl PieceTable {
+    pub fn offsets(&self, orientation: Orientation) -> PieceOffsets {
+        PieceOffsets {
+            offsets: self.offsets[orientation.index()].to_vec(),
+            pivot: self.pivots[orientation.index()],
+        }
+    }
+}
+
+// https://en.wikipedia.org/wiki/Blokus#/media/File:Blokus_tiles.svg
+piece_set! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[allow(dead_code)]
+    pub enum Piece {
+        One(O),
+        Two(OX),
+        ThreeL(OX, X_),
+        ThreeI(XOX),
+        FourI(XOXX),
+        FourL(OXX, X__),
+        FourStairs(XO_, _XX),
+        FourSquare(OX, XX),
+        FourT(XOX, _X_),
+        FiveF(X__, XOX, _X_),
+        FiveI(XXOXX),
+        FiveL(OXXX, X___),
+        FiveN(XXO_, __XX),
+        FiveP(XOX, _XX),
+        FiveT(XXX, _O_, _X_),
+        FiveU(XOX, X_X),
+        FiveV(OXX, X__, X__),
+        FiveW(XX_, _OX, __X),
+        FiveX(_X_, XOX, _X_),
+        FiveY(_X, XO, _X, _X),
+        FiveZ(XX_, _O_, _XX),
+        /// Loaded from a [`PieceSet`] file
+        #[custom]
+        Custom(CustomPiece),
+    }
+}
+
+impl Piece {
+    /// Orientations that cover different squares, so symmetric pieces only get placed once
+    pub fn orientations(self) -> &'static [Orientation] {
+        self.table().orientations
+    }
+
+    /// The orientation in [`Piece::orientations`] that looks the same as this one
+    pub fn canonical(self, orientation: Orientation) -> Orientation {
+        self.table().canonical[orientation.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_offsets(piece: Piece, orientation: Orientation) -> Vec<(i8, i8)> {
+        let mut offsets = piece.offsets(orientation).offsets;
+        offsets.sort();
+        offsets
+    }
+
+    #[test]
+    fn symmetric_orientations_are_listed_once() {
+        let counts = [
+            (Piece::One, 1),
+            (Piece::Two, 2),
+            (Piece::FourSquare, 1),
+            (Piece::FourStairs, 4),
+            (Piece::FiveI, 2),
+            (Piece::FiveT, 4),
+            (Piece::FiveX, 1),
+            (Piece::FiveF, 8),
+        ];
+        for (piece, count) in counts {
+            assert_eq!(piece.orientations().len(), count, "{piece:?}");
+        }
+        // Every fixed polyomino from one to five squares
+        let total: usize = Piece::iter().map(|p| p.orientations().len()).sum();
+        assert_eq!(total, 91);
+    }
+
+    #[test]
+    fn canonical_orientations_cover_the_same_squares() {
+        for piece in Piece::iter() {
+            let distinct: Vec<_> = piece
+                .orientations()
+                .iter()
+                .map(|o| sorted_offsets(piece, *o))
+                .collect();
+            for (i, offsets) in distinct.iter().enumerate() {
+                assert!(!distinct[..i].contains(offsets), "{piece:?}");
+            }
+            for orientation in Orientation::ALL {
+                let canonical = piece.canonical(orientation);
+                assert!(piece.orientations().contains(&canonical));
+                assert_eq!(
+                    sorted_offsets(piece, canonical),
+                    sorted_offsets(piece, orientation)
+                );
+            }
+        }
+    }
+}
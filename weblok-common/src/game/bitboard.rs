@@ -0,0 +1,372 @@
+use std::ops::{BitAnd, BitOr, Not};
+use weblok_shapes::MaskBits;
+
+use super::*;
+
+/// One bit per cell of a D by D board, bit x of row y is the cell (x, y)
+/// Rows are u32s, so boards can be up to 32 wide
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Bitboard<const D: usize>([u32; D]);
+
+impl<const D: usize> Bitboard<D> {
+    const FITS: () = assert!(D <= 32, "Bitboard rows are u32s");
+    const FULL_ROW: u32 = u32::MAX >> (32 - D);
+
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FITS;
+        Self([0; D])
+    }
+
+    pub fn full() -> Self {
+        Self([Self::FULL_ROW; D])
+    }
+
+    pub fn row(&self, y: usize) -> u32 {
+        self.0[y]
+    }
+
+    pub fn get(&self, x: i8, y: i8) -> bool {
+        self.0[y as usize] & (1 << x) != 0
+    }
+
+    pub fn set(&mut self, x: i8, y: i8) {
+        self.0[y as usize] |= 1 << x;
+    }
+
+    pub fn clear(&mut self, x: i8, y: i8) {
+        self.0[y as usize] &= !(1 << x);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|row| *row == 0)
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|row| row.count_ones()).sum()
+    }
+
+    /// Unset cells sharing an edge with a set cell
+    pub fn side_neighbors(&self) -> Self {
+        let mut out = Self::new();
+        for y in 0..D {
+            let above = if y > 0 { self.0[y - 1] } else { 0 };
+            let below = if y + 1 < D { self.0[y + 1] } else { 0 };
+            let row = self.0[y];
+            out.0[y] = (row << 1 | row >> 1 | above | below) & !row & Self::FULL_ROW;
+        }
+        out
+    }
+
+    /// Unset cells touching a set cell at a corner
+    pub fn corner_neighbors(&self) -> Self {
+        let mut out = Self::new();
+        for y in 0..D {
+            let above = if y > 0 { self.0[y - 1] } else { 0 };
+            let below = if y + 1 < D { self.0[y + 1] } else { 0 };
+            let diagonal = above << 1 | above >> 1 | below << 1 | below >> 1;
+            out.0[y] = diagonal & !self.0[y] & Self::FULL_ROW;
+        }
+        out
+    }
+
+    /// Set cells in row-major order
+    /// @return (x,y)
+    pub fn iter(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+        self.0.iter().enumerate().flat_map(|(y, row)| {
+            let mut bits = *row;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let x = bits.trailing_zeros();
+                bits &= bits - 1;
+                Some((x as i8, y as i8))
+            })
+        })
+    }
+}
+
+impl<const D: usize> Default for Bitboard<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize> BitOr for Bitboard<D> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        for y in 0..D {
+            self.0[y] |= rhs.0[y];
+        }
+        self
+    }
+}
+
+impl<const D: usize> BitAnd for Bitboard<D> {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        for y in 0..D {
+            self.0[y] &= rhs.0[y];
+        }
+        self
+    }
+}
+
+impl<const D: usize> Not for Bitboard<D> {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        for row in self.0.iter_mut() {
+            *row = !*row & Self::FULL_ROW;
+        }
+        self
+    }
+}
+
+pub use weblok_shapes::{MAX_PIECE_DIM, NEIGHBOR_DIM};
+
+/// A piece in one orientation as row masks, bit x of row y set for the offset (x, y)
+/// `piece!` generates these as constants
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PieceMask {
+    pub rows: [u8; MAX_PIECE_DIM],
+    /// Cells sharing an edge with the piece
+    /// Shifted one right and down, so bit 0 of row 0 is the offset (-1, -1)
+    pub side_neighbors: [u16; NEIGHBOR_DIM],
+    /// Cells touching the piece only at a corner, shifted like side_neighbors
+    pub corner_neighbors: [u16; NEIGHBOR_DIM],
+    /// Bounding box size
+    pub width: u8,
+    pub height: u8,
+}
+
+impl PieceMask {
+    /// Assumes the offsets are non-negative and fit in [`MAX_PIECE_DIM`]
+    pub fn from_offsets(offsets: &[(i8, i8)]) -> PieceMask {
+        let MaskBits {
+            rows,
+            side_neighbors,
+            corner_neighbors,
+            width,
+            height,
+        } = MaskBits::from_offsets(offsets);
+        PieceMask {
+            rows,
+            side_neighbors,
+            corner_neighbors,
+            width,
+            height,
+        }
+    }
+
+    /// @return (x,y)
+    pub fn offsets(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+        self.rows[..self.height as usize]
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                (0..8)
+                    .filter(move |x| row & (1 << x) != 0)
+                    .map(move |x| (x, y as i8))
+            })
+    }
+
+    /// Whether the piece stays on a D by D board with its bounding box at (col, row)
+    pub fn in_bounds<const D: usize>(&self, col: i8, row: i8) -> bool {
+        col >= 0
+            && row >= 0
+            && col as usize + self.width as usize <= D
+            && row as usize + self.height as usize <= D
+    }
+
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    pub fn overlaps<const D: usize>(&self, board: &Bitboard<D>, col: i8, row: i8) -> bool {
+        self.first_overlap(board, col, row).is_some()
+    }
+
+    /// First covered cell set on the board, in row-major order
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    /// @return (x,y)
+    pub fn first_overlap<const D: usize>(
+        &self,
+        board: &Bitboard<D>,
+        col: i8,
+        row: i8,
+    ) -> Option<(i8, i8)> {
+        for (dy, piece_row) in self.rows[..self.height as usize].iter().enumerate() {
+            let y = row as usize + dy;
+            let overlap = ((*piece_row as u32) << col) & board.row(y);
+            if overlap != 0 {
+                return Some((overlap.trailing_zeros() as i8, y as i8));
+            }
+        }
+        None
+    }
+
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    pub fn place_on<const D: usize>(&self, board: &mut Bitboard<D>, col: i8, row: i8) {
+        for (dy, piece_row) in self.rows[..self.height as usize].iter().enumerate() {
+            board.0[row as usize + dy] |= (*piece_row as u32) << col;
+        }
+    }
+
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    pub fn take_from<const D: usize>(&self, board: &mut Bitboard<D>, col: i8, row: i8) {
+        for (dy, piece_row) in self.rows[..self.height as usize].iter().enumerate() {
+            board.0[row as usize + dy] &= !((*piece_row as u32) << col);
+        }
+    }
+
+    /// The side neighbors of the placement that are on the board
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    pub fn side_neighbors_at<const D: usize>(&self, col: i8, row: i8) -> Bitboard<D> {
+        self.neighbors_at(&self.side_neighbors, col, row)
+    }
+
+    /// The corner neighbors of the placement that are on the board
+    /// Assumes the placement is [`PieceMask::in_bounds`]
+    pub fn corner_neighbors_at<const D: usize>(&self, col: i8, row: i8) -> Bitboard<D> {
+        self.neighbors_at(&self.corner_neighbors, col, row)
+    }
+
+    fn neighbors_at<const D: usize>(
+        &self,
+        neighbors: &[u16; NEIGHBOR_DIM],
+        col: i8,
+        row: i8,
+    ) -> Bitboard<D> {
+        let mut board = Bitboard::new();
+        for (dy, neighbor_row) in neighbors[..self.height as usize + 2].iter().enumerate() {
+            let y = row as usize + dy;
+            if y == 0 || y > D {
+                continue;
+            }
+            // Bit 0 is the column left of the piece
+            let bits = if col > 0 {
+                (*neighbor_row as u32) << (col - 1)
+            } else {
+                (*neighbor_row as u32) >> 1
+            };
+            board.0[y - 1] = bits & Bitboard::<D>::FULL_ROW;
+        }
+        board
+    }
+}
+
+impl Orientation {
+    /// Position in [`Orientation::ALL`]
+    pub fn index(self) -> usize {
+        self.rotation as usize + if self.flipped { 4 } else { 0 }
+    }
+}
+
+impl Piece {
+    pub fn mask(self, orientation: Orientation) -> &'static PieceMask {
+        &self.table().masks[orientation.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_bitboard<const D: usize>(rng: &mut StdRng) -> Bitboard<D> {
+        let mut board = Bitboard::new();
+        for y in 0..D as i8 {
+            for x in 0..D as i8 {
+                if rng.gen_bool(0.3) {
+                    board.set(x, y);
+                }
+            }
+        }
+        board
+    }
+
+    /// Unset cells on the board next to a set one in any of the directions
+    fn naive_neighbors<const D: usize>(
+        board: &Bitboard<D>,
+        directions: &[(i8, i8)],
+    ) -> Bitboard<D> {
+        let mut out = Bitboard::new();
+        for y in 0..D as i8 {
+            for x in 0..D as i8 {
+                let next_to_set = directions.iter().any(|(dx, dy)| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    (0..D as i8).contains(&nx) && (0..D as i8).contains(&ny) && board.get(nx, ny)
+                });
+                if !board.get(x, y) && next_to_set {
+                    out.set(x, y);
+                }
+            }
+        }
+        out
+    }
+
+    const SIDES: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    const CORNERS: [(i8, i8); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+    fn check_neighbors<const D: usize>() {
+        let mut rng = StdRng::seed_from_u64(D as u64);
+        for _ in 0..50 {
+            let board = random_bitboard::<D>(&mut rng);
+            assert_eq!(board.side_neighbors(), naive_neighbors(&board, &SIDES));
+            assert_eq!(board.corner_neighbors(), naive_neighbors(&board, &CORNERS));
+        }
+    }
+
+    #[test]
+    fn neighbors_match_cell_by_cell_check() {
+        check_neighbors::<DUO_DIM>();
+        check_neighbors::<CLASSIC_DIM>();
+        // Rows use every bit of the u32
+        check_neighbors::<32>();
+    }
+
+    #[test]
+    fn masks_match_piece_offsets() {
+        const D: usize = DUO_DIM;
+        let mut rng = StdRng::seed_from_u64(0);
+        let others = random_bitboard::<D>(&mut rng);
+
+        for piece in Piece::iter() {
+            for orientation in Orientation::ALL {
+                let mask = piece.mask(orientation);
+                for col in -1..D as i8 {
+                    for row in -1..D as i8 {
+                        let coords = coords_for_placement(piece, orientation, col, row);
+                        let on_board = coords
+                            .iter()
+                            .all(|(x, y)| (0..D as i8).contains(x) && (0..D as i8).contains(y));
+                        assert_eq!(mask.in_bounds::<D>(col, row), on_board);
+                        if !on_board {
+                            continue;
+                        }
+
+                        let mut board = Bitboard::<D>::new();
+                        mask.place_on(&mut board, col, row);
+                        let mut expected = Bitboard::new();
+                        coords.iter().for_each(|(x, y)| expected.set(*x, *y));
+                        assert_eq!(board, expected);
+
+                        let sides = board.side_neighbors();
+                        assert_eq!(mask.side_neighbors_at::<D>(col, row), sides);
+                        let corners = board.corner_neighbors() & !sides;
+                        assert_eq!(mask.corner_neighbors_at::<D>(col, row), corners);
+
+                        let first = (board & others).iter().next();
+                        assert_eq!(mask.first_overlap(&others, col, row), first);
+                        assert_eq!(mask.overlaps(&others, col, row), first.is_some());
+
+                        mask.take_from(&mut board, col, row);
+                        assert!(board.is_empty());
+                    }
+                }
+            }
+        }
+    }
+}
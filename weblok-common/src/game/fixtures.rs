@@ -0,0 +1,25 @@
+//! Random games for the game module's tests
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::*;
+
+/// Plays random legal moves, passing when there are none, until the game is over or
+/// `visit` returns true
+/// `visit` sees the game before every turn and once more at the end
+pub(crate) fn play_randomly<const D: usize>(
+    game: &mut GameState<D>,
+    seed: u64,
+    mut visit: impl FnMut(&GameState<D>) -> bool,
+) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    while !visit(game) {
+        let Some(occupancy) = game.current_player() else {
+            return;
+        };
+        match game.board().legal_moves(occupancy).choose(&mut rng) {
+            Some(&mv) => game.play(occupancy, mv).unwrap(),
+            None => game.pass(occupancy).unwrap(),
+        }
+    }
+}
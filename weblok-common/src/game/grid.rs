@@ -0,0 +1,77 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Rows of columns, indexed by (x, y)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Grid<T, const D: usize>([[T; D]; D]);
+
+impl<T: Default + Copy, const D: usize> Grid<T, D> {
+    pub fn new() -> Self {
+        Self([[T::default(); D]; D])
+    }
+
+    pub fn get(&self, x: i8, y: i8) -> T {
+        self.0[y as usize][x as usize]
+    }
+
+    pub fn get_opt(&self, x: i8, y: i8) -> Option<T> {
+        if x < 0 || x >= D as i8 || y < 0 || y >= D as i8 {
+            None
+        } else {
+            Some(self.0[y as usize][x as usize])
+        }
+    }
+
+    pub fn set(&mut self, x: i8, y: i8, value: T) {
+        self.0[y as usize][x as usize] = value;
+    }
+}
+
+impl<T: Default + Copy, const D: usize> Default for Grid<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Display, const D: usize> fmt::Display for Grid<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A sequence of rows, so boards of any size serialize the same way
+impl<T: Serialize, const D: usize> Serialize for Grid<T, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|row| row.as_slice()))
+    }
+}
+
+impl<'de, T, const D: usize> Deserialize<'de> for Grid<T, D>
+where
+    T: Deserialize<'de> + Default + Copy,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let rows = Vec::<Vec<T>>::deserialize(deserializer)?;
+        let expected = format!("{D} rows of {D} cells");
+        if rows.len() != D {
+            return Err(de::Error::invalid_length(rows.len(), &expected.as_str()));
+        }
+
+        let mut grid = Grid::new();
+        for (y, row) in rows.into_iter().enumerate() {
+            if row.len() != D {
+                return Err(de::Error::invalid_length(row.len(), &expected.as_str()));
+            }
+            grid.0[y].copy_from_slice(&row);
+        }
+        Ok(grid)
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// A placement as recorded in the [`Board`]'s move log
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Placement {
+    pub occupancy: Occupancy,
+    pub mv: Move,
+    /// Where the piece was in the inventory, so undoing restores the inventory order
+    pub(super) inventory_index: usize,
+}
+
+impl<const D: usize> Board<D> {
+    /// Placements in the order they were made
+    pub fn placements(&self) -> &[Placement] {
+        &self.history
+    }
+
+    /// Takes back the last placement, None if there is nothing to undo
+    pub fn undo(&mut self) -> Option<Placement> {
+        let placement = self.history.pop()?;
+        let Placement {
+            occupancy,
+            mv,
+            inventory_index,
+        } = placement;
+
+        self.toggle_zobrist_placement(occupancy, mv);
+
+        // Reverses the swap_remove in apply
+        let pieces = self
+            .inventories
+            .get_mut(&occupancy)
+            .expect("Got Occupancy::Empty");
+        pieces.push(mv.piece);
+        let last = pieces.len() - 1;
+        pieces.swap(inventory_index, last);
+
+        let mask = mv.piece.mask(mv.orientation);
+        for (x, y) in mask.offsets() {
+            self.occupancies
+                .set(mv.col + x, mv.row + y, Occupancy::Empty);
+        }
+        mask.take_from(&mut self.bitboards[occupancy as usize], mv.col, mv.row);
+        mask.take_from(&mut self.occupied, mv.col, mv.row);
+
+        self.redo.push(placement);
+        Some(placement)
+    }
+
+    /// Puts back the last undone placement, None if there is nothing to redo
+    /// Any new placement clears what there is to redo
+    pub fn redo(&mut self) -> Option<Placement> {
+        let placement = self.redo.pop()?;
+        self.apply(placement);
+        Some(placement)
+    }
+
+    /// Whether apply can take the placement: legal, and with the piece at its index
+    pub(super) fn can_apply(&self, placement: Placement) -> bool {
+        let Placement {
+            occupancy,
+            mv,
+            inventory_index,
+        } = placement;
+        let piece = self
+            .inventories
+            .get(&occupancy)
+            .and_then(|pieces| pieces.get(inventory_index));
+        piece == Some(&mv.piece)
+            && self.is_mask_valid(occupancy, mv.piece.mask(mv.orientation), mv.col, mv.row)
+    }
+
+    /// Assumes the placement is valid
+    pub(super) fn apply(&mut self, placement: Placement) {
+        let Placement {
+            occupancy,
+            mv,
+            inventory_index,
+        } = placement;
+
+        self.inventories
+            .get_mut(&occupancy)
+            .expect("Got Occupancy::Empty")
+            .swap_remove(inventory_index);
+
+        let mask = mv.piece.mask(mv.orientation);
+        for (x, y) in mask.offsets() {
+            self.occupancies.set(mv.col + x, mv.row + y, occupancy);
+        }
+        mask.place_on(&mut self.bitboards[occupancy as usize], mv.col, mv.row);
+        mask.place_on(&mut self.occupied, mv.col, mv.row);
+        self.toggle_zobrist_placement(occupancy, mv);
+
+        self.history.push(placement);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// Everything but the move log, which undo and redo are meant to change
+    type Position<const D: usize> = (
+        Grid<Occupancy, D>,
+        [Bitboard<D>; OCCUPANCY_COUNT],
+        Bitboard<D>,
+        HashMap<Occupancy, Vec<Piece>>,
+        u64,
+    );
+
+    fn position<const D: usize>(board: &Board<D>) -> Position<D> {
+        (
+            board.occupancies,
+            board.bitboards,
+            board.occupied,
+            board.inventories.clone(),
+            board.hash,
+        )
+    }
+
+    /// Plays a random game, then undoes and redoes all of it
+    fn check_undo_redo<const D: usize>(variant: Variant, pieces: &PieceSet, seed: u64) {
+        let mut game = GameState::<D>::with_piece_set(variant, variant.turn_order(), pieces);
+        let mut states = vec![];
+        play_randomly(&mut game, seed, |game| {
+            states.push(game.clone());
+            false
+        });
+        let finished = game.clone();
+
+        for state in states.iter().rev().skip(1) {
+            assert!(game.undo());
+            assert_eq!(position(game.board()), position(state.board()));
+            assert_eq!(game.history(), state.history());
+            assert_eq!(game.current_player(), state.current_player());
+            assert_eq!(game.zobrist_hash(), state.zobrist_hash());
+        }
+        assert!(!game.undo());
+
+        for state in &states[1..] {
+            assert!(game.redo());
+            assert_eq!(position(game.board()), position(state.board()));
+            assert_eq!(game.zobrist_hash(), state.zobrist_hash());
+        }
+        assert!(!game.redo());
+        assert_eq!(game, finished);
+    }
+
+    #[test]
+    fn undo_redo_restores_classic_games() {
+        for seed in 0..3 {
+            check_undo_redo::<CLASSIC_DIM>(Variant::Classic, &PieceSet::standard(), seed);
+        }
+    }
+
+    #[test]
+    fn undo_redo_restores_duo_games() {
+        for seed in 0..3 {
+            check_undo_redo::<DUO_DIM>(Variant::Duo, &PieceSet::standard(), seed);
+        }
+    }
+
+    #[test]
+    fn undo_redo_restores_inventory_order_with_duplicates() {
+        let pieces: PieceSet = "One\nFourI\nTwo\nFourI\nOne\nFiveF\nFourI".parse().unwrap();
+        for seed in 0..5 {
+            check_undo_redo::<DUO_DIM>(Variant::Duo, &pieces, seed);
+        }
+    }
+
+    #[test]
+    fn new_placements_clear_redo() {
+        let mut board = Board::<DUO_DIM>::with_variant(Variant::Duo);
+        let occupancy = Variant::Duo.turn_order()[0];
+        let moves = board.legal_moves(occupancy);
+        assert!(board.place_move(occupancy, moves[0]));
+        board.undo();
+        assert!(board.place_move(occupancy, moves[1]));
+        assert_eq!(board.redo(), None);
+    }
+}
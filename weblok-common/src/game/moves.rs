@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+
+use super::*;
+
+/// A single placement of a piece, independent of which occupancy plays it
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Move {
+    pub piece: Piece,
+    pub orientation: Orientation,
+    pub col: i8,
+    pub row: i8,
+}
+
+impl Move {
+    pub fn new(piece: Piece, orientation: Orientation, col: i8, row: i8) -> Move {
+        Move {
+            piece,
+            orientation,
+            col,
+            row,
+        }
+    }
+
+    /// @return (x,y)
+    pub fn coords(&self) -> Vec<(i8, i8)> {
+        coords_for_placement(self.piece, self.orientation, self.col, self.row)
+    }
+}
+
+impl<const D: usize> Board<D> {
+    // Returns false if the placement is invalid
+    pub fn place_move(&mut self, occupancy: Occupancy, mv: Move) -> bool {
+        self.place(occupancy, mv.piece, mv.orientation, mv.col, mv.row)
+    }
+
+    /// Every legal placement for the occupancy given its remaining inventory
+    pub fn legal_moves(&self, occupancy: Occupancy) -> Vec<Move> {
+        let mut moves = vec![];
+        let _ = self.visit_legal_moves(occupancy, |mv| {
+            moves.push(mv);
+            ControlFlow::Continue(())
+        });
+        moves
+    }
+
+    pub fn has_legal_move(&self, occupancy: Occupancy) -> bool {
+        self.visit_legal_moves(occupancy, |_| ControlFlow::Break(()))
+            .is_break()
+    }
+
+    /// Calls `visit` once per legal placement until it breaks
+    ///
+    /// Only placements covering an anchor are tried: every offset of every distinct
+    /// orientation is lined up with every anchor. A placement covering several anchors is
+    /// only reported from the first anchor it covers, so no deduplication set is needed.
+    pub fn visit_legal_moves(
+        &self,
+        occupancy: Occupancy,
+        mut visit: impl FnMut(Move) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let forbidden = self.forbidden(occupancy);
+        let anchors = self.anchors(occupancy);
+        if anchors.is_empty() {
+            return ControlFlow::Continue(());
+        }
+
+        for &piece in self.inventory(occupancy) {
+            for &orientation in piece.orientations() {
+                let mask = piece.mask(orientation);
+
+                for (dx, dy) in mask.offsets() {
+                    for (ax, ay) in anchors.iter() {
+                        let (col, row) = (ax - dx, ay - dy);
+                        if mask.in_bounds::<D>(col, row)
+                            && !mask.overlaps(&forbidden, col, row)
+                            && mask.first_overlap(&anchors, col, row) == Some((ax, ay))
+                        {
+                            visit(Move::new(piece, orientation, col, row))?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// The rules checked square by square on `occupancies`, without the validity grid
+    fn follows_rules<const D: usize>(
+        board: &Board<D>,
+        occupancy: Occupancy,
+        coords: &[(i8, i8)],
+    ) -> bool {
+        let owner = |x: i8, y: i8| board.occupancies.get_opt(x, y);
+        let start = board.start_point(occupancy);
+        let started = start.is_some_and(|(x, y)| owner(x, y) == Some(occupancy));
+
+        let mut anchored = false;
+        for &(x, y) in coords {
+            let sides = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)];
+            if owner(x, y) != Some(Occupancy::Empty)
+                || sides.iter().any(|(x, y)| owner(*x, *y) == Some(occupancy))
+            {
+                return false;
+            }
+            let corners = [
+                (x + 1, y + 1),
+                (x - 1, y + 1),
+                (x + 1, y - 1),
+                (x - 1, y - 1),
+            ];
+            anchored |= match started {
+                true => corners
+                    .iter()
+                    .any(|(x, y)| owner(*x, *y) == Some(occupancy)),
+                false => start == Some((x, y)),
+            };
+        }
+        anchored
+    }
+
+    /// Every piece in every orientation at every position, whether in bounds or not
+    fn brute_force_moves<const D: usize>(board: &Board<D>, occupancy: Occupancy) -> HashSet<Move> {
+        let mut moves = HashSet::new();
+        for &piece in board.inventory(occupancy) {
+            for &orientation in piece.orientations() {
+                for col in -5..D as i8 {
+                    for row in -5..D as i8 {
+                        let coords = coords_for_placement(piece, orientation, col, row);
+                        if follows_rules(board, occupancy, &coords) {
+                            moves.insert(Move::new(piece, orientation, col, row));
+                        }
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    /// Checks every player's moves after each turn of a random game
+    fn check_random_game<const D: usize>(variant: Variant, seed: u64) {
+        play_randomly(&mut GameState::<D>::with_variant(variant), seed, |game| {
+            for &occupancy in game.turn_order() {
+                let board = game.board();
+                let moves = board.legal_moves(occupancy);
+                let unique: HashSet<_> = moves.iter().copied().collect();
+
+                assert_eq!(unique.len(), moves.len(), "moves are listed once");
+                assert_eq!(unique, brute_force_moves(board, occupancy));
+                assert_eq!(board.has_legal_move(occupancy), !moves.is_empty());
+                for mv in moves {
+                    assert!(board.clone().place_move(occupancy, mv));
+                }
+            }
+            false
+        });
+    }
+
+    #[test]
+    fn duo_moves_match_brute_force() {
+        for seed in 0..3 {
+            check_random_game::<DUO_DIM>(Variant::Duo, seed);
+        }
+    }
+
+    #[test]
+    fn classic_moves_match_brute_force() {
+        check_random_game::<CLASSIC_DIM>(Variant::Classic, 0);
+    }
+
+    #[test]
+    fn duo_fits_small_boards() {
+        assert_eq!(Variant::Duo.start_point(Occupancy::Violet, 4), Some((1, 1)));
+        assert_eq!(Variant::Duo.start_point(Occupancy::Orange, 4), Some((2, 2)));
+        assert_eq!(Variant::Duo.start_point(Occupancy::Orange, 9), Some((5, 5)));
+        check_random_game::<4>(Variant::Duo, 0);
+        check_random_game::<5>(Variant::Duo, 0);
+        check_random_game::<9>(Variant::Duo, 0);
+    }
+}
@@ -0,0 +1,629 @@
+//! Text notation for moves and whole games
+//!
+//! A turn is written as the player's letter, the piece, the orientation and the square of
+//! the top-left corner of the piece's bounding box, e.g. `G FiveX R90 e7`, or `B pass`.
+//! Orientations are `R0`, `R90`, `R180` and `R270`, with `F` instead of `R` once flipped.
+//! Columns are letters from `a` and rows are numbers from `1`, so `a1` is (0, 0).
+//! Columns after `z` go on like spreadsheet columns: `aa`, `ab` and so on.
+//!
+//! A game record is a list of `[Tag "value"]` headers followed by numbered turns:
+//!
+//! ```text
+//! [Variant "Duo"]
+//! [Order "V O"]
+//! [Violet "Ash"]
+//! [Orange "Misty"]
+//! [Date "2022-05-01"]
+//! [Scoring "Advanced"]
+//! [Result "V 15 O -4"]
+//!
+//! 1. V FiveX R0 d4
+//! 2. O FourL R90 j10
+//! ```
+//!
+//! Forced passes are left out, replaying the record brings them back. Games played with
+//! anything but the standard pieces get a `Pieces` header holding the piece set file,
+//! with `;` between its lines, e.g. `[Pieces "FourI; SixI: XXOXXX; [Blue]; FourI"]`.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use super::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotationError {
+    UnknownOccupancy(String),
+    UnknownPiece(String),
+    UnknownOrientation(String),
+    UnknownVariant(String),
+    UnknownScoring(String),
+    BadSquare(String),
+    BadTurn(String),
+    BadHeader(String),
+    BadResult(String),
+    /// Empty, repeating a player or with a player the variant does not have
+    BadTurnOrder(String),
+    /// Why the `Pieces` header could not be read
+    BadPieces(String),
+    /// The turn numbered `ply` could not be played
+    IllegalTurn {
+        ply: usize,
+        error: MoveError,
+    },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::UnknownOccupancy(s) => write!(f, "unknown color `{s}`"),
+            NotationError::UnknownPiece(s) => write!(f, "unknown piece `{s}`"),
+            NotationError::UnknownOrientation(s) => write!(f, "unknown orientation `{s}`"),
+            NotationError::UnknownVariant(s) => write!(f, "unknown variant `{s}`"),
+            NotationError::UnknownScoring(s) => write!(f, "unknown scoring `{s}`"),
+            NotationError::BadSquare(s) => write!(f, "bad square `{s}`"),
+            NotationError::BadTurn(s) => write!(f, "bad turn `{s}`"),
+            NotationError::BadHeader(s) => write!(f, "bad header `{s}`"),
+            NotationError::BadResult(s) => write!(f, "bad result `{s}`"),
+            NotationError::BadTurnOrder(s) => write!(f, "bad turn order `{s}`"),
+            NotationError::BadPieces(s) => write!(f, "bad piece set: {s}"),
+            NotationError::IllegalTurn { ply, error } => write!(f, "turn {ply}: {error}"),
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+impl FromStr for Occupancy {
+    type Err = NotationError;
+
+    /// Parses the letters [`Occupancy`] displays as
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "G" => Occupancy::Green,
+            "R" => Occupancy::Red,
+            "B" => Occupancy::Blue,
+            "Y" => Occupancy::Yellow,
+            "V" => Occupancy::Violet,
+            "O" => Occupancy::Orange,
+            _ => return Err(NotationError::UnknownOccupancy(s.to_string())),
+        })
+    }
+}
+
+impl Piece {
+    pub fn name(self) -> String {
+        match self {
+            Piece::Custom(custom) => custom.name().to_string(),
+            standard => format!("{standard:?}"),
+        }
+    }
+}
+
+impl FromStr for Piece {
+    type Err = NotationError;
+
+    /// Custom pieces have to be defined first, see [`CustomPiece::define`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Piece::iter()
+            .find(|piece| piece.name() == s)
+            .or_else(|| CustomPiece::find(s).map(Piece::Custom))
+            .ok_or_else(|| NotationError::UnknownPiece(s.to_string()))
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.flipped { 'F' } else { 'R' };
+        let degrees = match self.rotation {
+            Rotation::Zero => 0,
+            Rotation::Ninety => 90,
+            Rotation::OneEighty => 180,
+            Rotation::TwoSeventy => 270,
+        };
+        write!(f, "{prefix}{degrees}")
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Orientation::ALL
+            .into_iter()
+            .find(|orientation| orientation.to_string() == s)
+            .ok_or_else(|| NotationError::UnknownOrientation(s.to_string()))
+    }
+}
+
+/// e.g. (4, 6) is `e7`, and (27, 0) is `ab1`
+pub fn format_square(col: i8, row: i8) -> String {
+    let mut letters = vec![];
+    let mut n = col as u32 + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect::<String>() + &(row + 1).to_string()
+}
+
+/// @return (col,row)
+pub fn parse_square(s: &str) -> Result<(i8, i8), NotationError> {
+    let bad_square = || NotationError::BadSquare(s.to_string());
+
+    let letters_end = s.find(|c: char| !c.is_ascii_lowercase()).unwrap_or(s.len());
+    let (letters, row) = s.split_at(letters_end);
+    if letters.is_empty() {
+        return Err(bad_square());
+    }
+    let col = letters
+        .bytes()
+        .try_fold(0u32, |n, c| {
+            n.checked_mul(26)?.checked_add((c - b'a') as u32 + 1)
+        })
+        .and_then(|n| i8::try_from(n - 1).ok())
+        .ok_or_else(bad_square)?;
+    let row: i8 = row.parse().map_err(|_| bad_square())?;
+    if row < 1 {
+        return Err(bad_square());
+    }
+    Ok((col, row - 1))
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.piece.name(),
+            self.orientation,
+            format_square(self.col, self.row)
+        )
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [piece, orientation, square] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(NotationError::BadTurn(s.to_string()));
+        };
+        let (col, row) = parse_square(square)?;
+        Ok(Move::new(piece.parse()?, orientation.parse()?, col, row))
+    }
+}
+
+/// Forced and explicit passes are both written as `pass`
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Turn::Placed { occupancy, mv } => write!(f, "{occupancy} {mv}"),
+            Turn::Passed { occupancy, .. } => write!(f, "{occupancy} pass"),
+        }
+    }
+}
+
+/// Passes are read as explicit
+impl FromStr for Turn {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((occupancy, rest)) = s.trim().split_once(' ') else {
+            return Err(NotationError::BadTurn(s.to_string()));
+        };
+        let occupancy = occupancy.parse()?;
+        Ok(match rest.trim() {
+            "pass" => Turn::Passed {
+                occupancy,
+                forced: false,
+            },
+            mv => Turn::Placed {
+                occupancy,
+                mv: mv.parse()?,
+            },
+        })
+    }
+}
+
+impl FromStr for Variant {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Classic" => Ok(Variant::Classic),
+            "Duo" => Ok(Variant::Duo),
+            _ => Err(NotationError::UnknownVariant(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Basic" => Ok(Scoring::Basic),
+            "Advanced" => Ok(Scoring::Advanced),
+            _ => Err(NotationError::UnknownScoring(s.to_string())),
+        }
+    }
+}
+
+/// A written down game, see the [module docs](self) for the format
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GameRecord {
+    pub variant: Variant,
+    pub turn_order: Vec<Occupancy>,
+    /// Player names in turn order, players without one are left out
+    pub players: Vec<(Occupancy, String)>,
+    pub date: Option<String>,
+    /// What every player started with
+    pub pieces: PieceSet,
+    pub result: Option<GameResult>,
+    /// Headers this format does not know about, kept as they are
+    pub other_headers: Vec<(String, String)>,
+    /// Moves and explicit passes
+    pub turns: Vec<Turn>,
+}
+
+impl GameRecord {
+    pub fn new(variant: Variant) -> GameRecord {
+        GameRecord {
+            variant,
+            turn_order: variant.turn_order(),
+            ..Default::default()
+        }
+    }
+
+    /// Records the turns played so far, along with the result if the game is over
+    pub fn from_game<const D: usize>(game: &GameState<D>) -> GameRecord {
+        GameRecord {
+            variant: game.variant(),
+            turn_order: game.turn_order().to_vec(),
+            pieces: starting_pieces(game.board()),
+            result: game.result(game.variant().scoring()),
+            turns: game
+                .history()
+                .iter()
+                .filter(|turn| !matches!(turn, Turn::Passed { forced: true, .. }))
+                .copied()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn player(&self, occupancy: Occupancy) -> Option<&str> {
+        self.players
+            .iter()
+            .find(|(o, _)| *o == occupancy)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Plays the turns on a fresh game
+    pub fn replay<const D: usize>(&self) -> Result<GameState<D>, NotationError> {
+        check_turn_order(self.variant, &self.turn_order)?;
+        let mut game =
+            GameState::with_piece_set(self.variant, self.turn_order.clone(), &self.pieces);
+        for (i, turn) in self.turns.iter().enumerate() {
+            match *turn {
+                Turn::Placed { occupancy, mv } => game.play(occupancy, mv),
+                Turn::Passed { occupancy, .. } => game.pass(occupancy),
+            }
+            .map_err(|error| NotationError::IllegalTurn { ply: i + 1, error })?;
+        }
+        Ok(game)
+    }
+
+    fn format_headers(&self) -> Vec<(String, String)> {
+        let letters = |occupancies: &mut dyn Iterator<Item = &Occupancy>| {
+            occupancies
+                .map(|o| o.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut headers = vec![
+            ("Variant".to_string(), format!("{:?}", self.variant)),
+            ("Order".to_string(), letters(&mut self.turn_order.iter())),
+        ];
+        for (occupancy, name) in &self.players {
+            headers.push((format!("{occupancy:?}"), name.clone()));
+        }
+        if let Some(date) = &self.date {
+            headers.push(("Date".to_string(), date.clone()));
+        }
+        if self.pieces != PieceSet::standard() {
+            let pieces = self.pieces.to_string();
+            let lines: Vec<_> = pieces.lines().collect();
+            headers.push(("Pieces".to_string(), lines.join("; ")));
+        }
+        if let Some(result) = &self.result {
+            let scores = result
+                .scores
+                .iter()
+                .map(|(o, score)| format!("{o} {score}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            headers.push(("Scoring".to_string(), format!("{:?}", result.scoring)));
+            headers.push(("Result".to_string(), scores));
+        }
+        headers.extend(self.other_headers.iter().cloned());
+        headers
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tag, value) in self.format_headers() {
+            writeln!(f, "[{tag} \"{value}\"]")?;
+        }
+        writeln!(f)?;
+        for (i, turn) in self.turns.iter().enumerate() {
+            writeln!(f, "{}. {turn}", i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new(Variant::default());
+        let mut turn_order = None;
+        let mut scoring = Scoring::default();
+        let mut scores = None;
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if line.starts_with('[') {
+                let (tag, value) = parse_header(line)?;
+                match tag {
+                    "Variant" => record.variant = value.parse()?,
+                    "Order" => turn_order = Some(parse_letters(value)?),
+                    "Date" => record.date = Some(value.to_string()),
+                    "Pieces" => {
+                        record.pieces = value
+                            .replace(';', "\n")
+                            .parse()
+                            .map_err(|e: PieceSetError| NotationError::BadPieces(e.to_string()))?
+                    }
+                    "Scoring" => scoring = value.parse()?,
+                    "Result" => scores = Some(parse_scores(value)?),
+                    _ => match occupancy_by_name(tag) {
+                        Some(occupancy) => record.players.push((occupancy, value.to_string())),
+                        None => record
+                            .other_headers
+                            .push((tag.to_string(), value.to_string())),
+                    },
+                }
+                continue;
+            }
+
+            let turn = match line.split_once(". ") {
+                Some((number, turn)) if number.parse::<usize>().is_ok() => turn,
+                _ => return Err(NotationError::BadTurn(line.to_string())),
+            };
+            record.turns.push(turn.parse()?);
+        }
+
+        record.turn_order = turn_order.unwrap_or_else(|| record.variant.turn_order());
+        check_turn_order(record.variant, &record.turn_order)?;
+        record.result = scores.map(|scores: Vec<(Occupancy, i32)>| {
+            let best = scores.iter().map(|(_, score)| *score).max();
+            let winners = scores
+                .iter()
+                .filter(|(_, score)| Some(*score) == best)
+                .map(|(o, _)| *o)
+                .collect();
+            GameResult {
+                scoring,
+                scores,
+                winners,
+            }
+        });
+        Ok(record)
+    }
+}
+
+/// What the players had before the first placement, the most common set as the base
+fn starting_pieces<const D: usize>(board: &Board<D>) -> PieceSet {
+    let mut start = board.clone();
+    while start.undo().is_some() {}
+    let players = start.variant().turn_order();
+    let sharing = |pieces: &[Piece]| {
+        players
+            .iter()
+            .filter(|o| start.inventory(**o) == pieces)
+            .count()
+    };
+    let base = players
+        .iter()
+        .map(|o| start.inventory(*o))
+        // The first player's on ties, since max_by_key keeps the last
+        .rev()
+        .max_by_key(|pieces| sharing(pieces))
+        .expect("variants have players");
+
+    let mut set = PieceSet::new(base.to_vec());
+    for &occupancy in &players {
+        if start.inventory(occupancy) != base {
+            set = set.with_handicap(occupancy, start.inventory(occupancy).to_vec());
+        }
+    }
+    set
+}
+
+fn check_turn_order(variant: Variant, turn_order: &[Occupancy]) -> Result<(), NotationError> {
+    if variant.is_valid_turn_order(turn_order) {
+        return Ok(());
+    }
+    let letters: Vec<_> = turn_order.iter().map(|o| o.to_string()).collect();
+    Err(NotationError::BadTurnOrder(letters.join(" ")))
+}
+
+/// `[Tag "value"]`
+fn parse_header(line: &str) -> Result<(&str, &str), NotationError> {
+    let bad_header = || NotationError::BadHeader(line.to_string());
+
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(bad_header)?;
+    let (tag, value) = inner.split_once(' ').ok_or_else(bad_header)?;
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(bad_header)?;
+    Ok((tag, value))
+}
+
+/// Player names are tagged with the color's full name
+pub(super) fn occupancy_by_name(name: &str) -> Option<Occupancy> {
+    [
+        Occupancy::Green,
+        Occupancy::Red,
+        Occupancy::Blue,
+        Occupancy::Yellow,
+        Occupancy::Violet,
+        Occupancy::Orange,
+    ]
+    .into_iter()
+    .find(|o| format!("{o:?}") == name)
+}
+
+fn parse_letters(s: &str) -> Result<Vec<Occupancy>, NotationError> {
+    s.split_whitespace().map(str::parse).collect()
+}
+
+/// `B -3 Y 0 R -12 G 15`
+fn parse_scores(s: &str) -> Result<Vec<(Occupancy, i32)>, NotationError> {
+    let words: Vec<_> = s.split_whitespace().collect();
+    if words.len() % 2 != 0 {
+        return Err(NotationError::BadResult(s.to_string()));
+    }
+    words
+        .chunks(2)
+        .map(|pair| {
+            let score = pair[1]
+                .parse()
+                .map_err(|_| NotationError::BadResult(s.to_string()))?;
+            Ok((pair[0].parse()?, score))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// The record in the module docs
+    const EXAMPLE: &str = r#"[Variant "Duo"]
+[Order "V O"]
+[Violet "Ash"]
+[Orange "Misty"]
+[Date "2022-05-01"]
+[Scoring "Advanced"]
+[Result "V 15 O -4"]
+
+1. V FiveX R0 d4
+2. O FourL R90 j10
+"#;
+
+    fn random_game<const D: usize>(variant: Variant, seed: u64) -> GameState<D> {
+        let mut game = GameState::with_variant(variant);
+        play_randomly(&mut game, seed, |_| false);
+        game
+    }
+
+    #[test]
+    fn example_record_parses_and_replays() {
+        let record: GameRecord = EXAMPLE.parse().unwrap();
+        assert_eq!(record.variant, Variant::Duo);
+        assert_eq!(record.player(Occupancy::Orange), Some("Misty"));
+        assert_eq!(record.date.as_deref(), Some("2022-05-01"));
+        let game = record.replay::<DUO_DIM>().unwrap();
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(record.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn squares_round_trip() {
+        // As wide as a bitboard goes
+        for col in 0..32 {
+            for row in 0..32 {
+                assert_eq!(parse_square(&format_square(col, row)), Ok((col, row)));
+            }
+        }
+        assert_eq!(format_square(25, 0), "z1");
+        assert_eq!(format_square(26, 0), "aa1");
+        assert_eq!(format_square(31, 31), "af32");
+        for bad in ["", "a", "a0", "A1", "1a", "a-1", "zzzzzz1"] {
+            assert_eq!(parse_square(bad), Err(NotationError::BadSquare(bad.into())));
+        }
+    }
+
+    #[test]
+    fn turns_round_trip() {
+        for orientation in Orientation::ALL {
+            assert_eq!(orientation.to_string().parse(), Ok(orientation));
+        }
+        let game = random_game::<CLASSIC_DIM>(Variant::Classic, 0);
+        for turn in game.history() {
+            let parsed: Turn = turn.to_string().parse().unwrap();
+            match (parsed, turn) {
+                (Turn::Passed { occupancy, .. }, Turn::Passed { occupancy: o, .. }) => {
+                    assert_eq!(occupancy, *o)
+                }
+                _ => assert_eq!(parsed, *turn),
+            }
+        }
+    }
+
+    #[test]
+    fn records_round_trip_and_replay() {
+        for seed in 0..3 {
+            let game = random_game::<DUO_DIM>(Variant::Duo, seed);
+            let mut record = GameRecord::from_game(&game);
+            record.players = vec![(Occupancy::Violet, "Ash".into())];
+            record.date = Some("2022-05-01".into());
+            record.other_headers = vec![("Event".into(), "Test".into())];
+
+            let parsed: GameRecord = record.to_string().parse().unwrap();
+            assert_eq!(parsed, record);
+            assert_eq!(parsed.replay::<DUO_DIM>().unwrap(), game);
+        }
+    }
+
+    #[test]
+    fn bad_orders_are_rejected() {
+        for order in ["", "V V", "V G", "V O O"] {
+            let text = format!("[Variant \"Duo\"]\n[Order \"{order}\"]\n");
+            assert!(matches!(
+                text.parse::<GameRecord>(),
+                Err(NotationError::BadTurnOrder(_))
+            ));
+        }
+        let mut record = GameRecord::new(Variant::Classic);
+        record.turn_order.pop();
+        record.turn_order.push(Occupancy::Violet);
+        assert!(matches!(
+            record.replay::<CLASSIC_DIM>(),
+            Err(NotationError::BadTurnOrder(_))
+        ));
+    }
+
+    #[test]
+    fn illegal_turns_report_their_ply() {
+        let text = "[Variant \"Duo\"]\n\n1. V FiveX R0 d4\n2. V One R0 a1\n";
+        let record: GameRecord = text.parse().unwrap();
+        assert!(matches!(
+            record.replay::<DUO_DIM>(),
+            Err(NotationError::IllegalTurn { ply: 2, .. })
+        ));
+        assert_eq!(
+            "1. V Nope R0 a1".parse::<GameRecord>(),
+            Err(NotationError::UnknownPiece("Nope".into()))
+        );
+    }
+}
@@ -0,0 +1,489 @@
+//! Pieces defined at runtime and the sets players start with
+//!
+//! A piece set file lists one piece per line, either the name of a standard piece or a
+//! new piece written with the same masks `piece!` takes, e.g. `SixL: OXXXX, X____`.
+//! Lines after a `[Blue]` style header only apply to that color, which gives handicaps.
+//!
+//! ```text
+//! # Tetrominoes for everyone, Blue also gets the long hexomino
+//! FourI
+//! FourL
+//! FourStairs
+//! FourSquare
+//! FourT
+//!
+//! [Blue]
+//! FourI
+//! FourL
+//! FourStairs
+//! FourSquare
+//! FourT
+//! SixI: XXOXXX
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::Path,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+use weblok_shapes::{Shape, ShapeError};
+
+use super::*;
+
+#[derive(Debug)]
+pub enum PieceSetError {
+    Io(io::Error),
+    UnknownPiece(String),
+    UnknownOccupancy(String),
+    /// Names are written in turns, so they have to be identifiers other than `pass`
+    BadName(String),
+    /// Standard pieces and other custom pieces can't be redefined
+    NameTaken(String),
+    InvalidChar {
+        piece: String,
+        c: char,
+    },
+    UnevenRows(String),
+    /// Needs at least one `X` or `O`
+    NoSquares(String),
+    /// Needs exactly one `O`
+    Pivot(String),
+    /// Every square has to share an edge with another one
+    Disconnected(String),
+    TooLarge(String),
+    /// [`MAX_CUSTOM_PIECES`] are already defined
+    TooManyPieces(String),
+}
+
+impl fmt::Display for PieceSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PieceSetError::Io(e) => write!(f, "could not read the piece set: {e}"),
+            PieceSetError::UnknownPiece(name) => write!(f, "unknown piece `{name}`"),
+            PieceSetError::UnknownOccupancy(name) => write!(f, "unknown color `{name}`"),
+            PieceSetError::BadName(name) => write!(f, "`{name}` can't be a piece name"),
+            PieceSetError::NameTaken(name) => write!(f, "`{name}` is already a piece"),
+            PieceSetError::InvalidChar { piece, c } => {
+                write!(f, "invalid char `{c}` in `{piece}`")
+            }
+            PieceSetError::UnevenRows(piece) => {
+                write!(f, "every row of `{piece}` has to be the same length")
+            }
+            PieceSetError::NoSquares(piece) => write!(f, "`{piece}` has no squares"),
+            PieceSetError::Pivot(piece) => write!(f, "`{piece}` needs exactly one pivot"),
+            PieceSetError::Disconnected(piece) => write!(f, "`{piece}` is not connected"),
+            PieceSetError::TooLarge(piece) => write!(
+                f,
+                "`{piece}` does not fit in {MAX_PIECE_DIM} by {MAX_PIECE_DIM} squares"
+            ),
+            PieceSetError::TooManyPieces(piece) => write!(
+                f,
+                "can't define `{piece}`, there are already {MAX_CUSTOM_PIECES} custom pieces"
+            ),
+        }
+    }
+}
+
+impl Error for PieceSetError {}
+
+impl From<io::Error> for PieceSetError {
+    fn from(e: io::Error) -> Self {
+        PieceSetError::Io(e)
+    }
+}
+
+/// A piece's shape in all eight orientations
+#[derive(Debug)]
+pub struct PieceShape {
+    name: String,
+    /// The mask as written, e.g. `OX, X_`
+    rows: String,
+    square_count: u8,
+    table: PieceTable,
+}
+
+impl PieceShape {
+    /// Builds the orientations the same way `piece!` does at compile time
+    fn parse(name: &str, rows: &str) -> Result<PieceShape, PieceSetError> {
+        let mask: Vec<&str> = rows
+            .split(',')
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        let shape = Shape::parse(&mask).map_err(|e| {
+            let name = name.to_string();
+            match e {
+                ShapeError::InvalidChar { c, .. } => PieceSetError::InvalidChar { piece: name, c },
+                ShapeError::UnevenRows { .. } => PieceSetError::UnevenRows(name),
+                ShapeError::TooLarge { .. } => PieceSetError::TooLarge(name),
+                ShapeError::NoSquares => PieceSetError::NoSquares(name),
+                ShapeError::NoPivot | ShapeError::ExtraPivot { .. } => PieceSetError::Pivot(name),
+                ShapeError::Disconnected { .. } => PieceSetError::Disconnected(name),
+            }
+        })?;
+
+        let orientations = Orientation::ALL
+            .into_iter()
+            .filter(|o| shape.canonical[o.index()] == o.index())
+            .collect::<Vec<_>>();
+        Ok(PieceShape {
+            name: name.to_string(),
+            rows: normalize_rows(rows).to_string(),
+            square_count: shape.square_count,
+            table: PieceTable {
+                orientations: orientations.leak(),
+                canonical: shape.canonical.map(|i| Orientation::ALL[i]),
+                pivots: shape.orientations.each_ref().map(|o| o.pivot),
+                masks: shape
+                    .orientations
+                    .each_ref()
+                    .map(|o| PieceMask::from_offsets(&o.offsets)),
+                offsets: shape.orientations.map(|o| &*o.offsets.leak()),
+            },
+        })
+    }
+}
+
+/// The mask without surrounding whitespace or a trailing comma, as it's stored
+fn normalize_rows(rows: &str) -> &str {
+    rows.trim().trim_end_matches(',').trim()
+}
+
+/// Letters, digits and underscores, not starting with a digit, and not `pass`
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "pass"
+}
+
+/// Shapes are never freed, so this bounds what untrusted piece sets can allocate
+pub const MAX_CUSTOM_PIECES: usize = 256;
+
+/// Every custom piece defined so far, so each name maps to a single shape
+fn custom_pieces() -> &'static Mutex<Vec<&'static PieceShape>> {
+    static CUSTOM_PIECES: OnceLock<Mutex<Vec<&'static PieceShape>>> = OnceLock::new();
+    CUSTOM_PIECES.get_or_init(Default::default)
+}
+
+/// A piece defined at runtime
+/// Shapes live for the rest of the program, so custom pieces are as cheap to copy as
+/// standard ones
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "CustomPieceDef", into = "CustomPieceDef")]
+pub struct CustomPiece(&'static PieceShape);
+
+/// What a [`CustomPiece`] serializes as, so it can be defined again when read back
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "CustomPiece")]
+struct CustomPieceDef {
+    name: String,
+    rows: String,
+}
+
+impl CustomPiece {
+    /// Defining a name again with the same rows returns the existing piece
+    pub fn define(name: &str, rows: &str) -> Result<CustomPiece, PieceSetError> {
+        if !is_valid_name(name) {
+            return Err(PieceSetError::BadName(name.to_string()));
+        }
+        if Piece::iter().any(|piece| piece.name() == name) {
+            return Err(PieceSetError::NameTaken(name.to_string()));
+        }
+
+        // Only new shapes get built, since building one leaks its tables
+        let mut pieces = custom_pieces().lock().unwrap();
+        if let Some(existing) = pieces.iter().find(|s| s.name == name) {
+            return if existing.rows == normalize_rows(rows) {
+                Ok(CustomPiece(existing))
+            } else {
+                Err(PieceSetError::NameTaken(name.to_string()))
+            };
+        }
+        if pieces.len() >= MAX_CUSTOM_PIECES {
+            return Err(PieceSetError::TooManyPieces(name.to_string()));
+        }
+        let shape = Box::leak(Box::new(PieceShape::parse(name, rows)?));
+        pieces.push(shape);
+        Ok(CustomPiece(shape))
+    }
+
+    /// A custom piece defined earlier
+    pub fn find(name: &str) -> Option<CustomPiece> {
+        let pieces = custom_pieces().lock().unwrap();
+        pieces
+            .iter()
+            .find(|shape| shape.name == name)
+            .map(|shape| CustomPiece(shape))
+    }
+
+    pub fn name(self) -> &'static str {
+        &self.0.name
+    }
+
+    /// The mask it was defined with
+    pub fn rows(self) -> &'static str {
+        &self.0.rows
+    }
+
+    pub fn square_count(self) -> u8 {
+        self.0.square_count
+    }
+
+    pub fn table(self) -> &'static PieceTable {
+        &self.0.table
+    }
+}
+
+impl PartialEq for CustomPiece {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for CustomPiece {}
+
+impl std::hash::Hash for CustomPiece {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.name.hash(state);
+    }
+}
+
+impl fmt::Debug for CustomPiece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl TryFrom<CustomPieceDef> for CustomPiece {
+    type Error = PieceSetError;
+
+    fn try_from(def: CustomPieceDef) -> Result<Self, Self::Error> {
+        CustomPiece::define(&def.name, &def.rows)
+    }
+}
+
+impl From<CustomPiece> for CustomPieceDef {
+    fn from(piece: CustomPiece) -> Self {
+        CustomPieceDef {
+            name: piece.name().to_string(),
+            rows: piece.rows().to_string(),
+        }
+    }
+}
+
+/// The pieces each player starts with
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PieceSet {
+    pieces: Vec<Piece>,
+    /// Players starting with something else
+    handicaps: Vec<(Occupancy, Vec<Piece>)>,
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        PieceSet::standard()
+    }
+}
+
+impl PieceSet {
+    /// The 21 pieces of the board game
+    pub fn standard() -> PieceSet {
+        PieceSet::new(Piece::ALL.to_vec())
+    }
+
+    pub fn new(pieces: Vec<Piece>) -> PieceSet {
+        PieceSet {
+            pieces,
+            handicaps: vec![],
+        }
+    }
+
+    /// Gives one player different pieces from everyone else
+    pub fn with_handicap(mut self, occupancy: Occupancy, pieces: Vec<Piece>) -> PieceSet {
+        self.handicaps.retain(|(o, _)| *o != occupancy);
+        self.handicaps.push((occupancy, pieces));
+        self
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<PieceSet, PieceSetError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// What everyone without a handicap starts with
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    pub fn pieces_for(&self, occupancy: Occupancy) -> &[Piece] {
+        self.handicaps
+            .iter()
+            .find(|(o, _)| *o == occupancy)
+            .map_or(&self.pieces, |(_, pieces)| pieces)
+    }
+}
+
+/// The format [`PieceSet::from_str`] reads back
+impl fmt::Display for PieceSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_pieces = |f: &mut fmt::Formatter<'_>, pieces: &[Piece]| {
+            pieces.iter().try_for_each(|piece| match piece {
+                Piece::Custom(custom) => writeln!(f, "{}: {}", custom.name(), custom.rows()),
+                _ => writeln!(f, "{}", piece.name()),
+            })
+        };
+        write_pieces(f, &self.pieces)?;
+        for (occupancy, pieces) in &self.handicaps {
+            writeln!(f, "[{occupancy:?}]")?;
+            write_pieces(f, pieces)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PieceSet {
+    type Err = PieceSetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = PieceSet::new(vec![]);
+        let mut section: Option<Occupancy> = None;
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let occupancy = occupancy_by_name(name.trim())
+                    .ok_or_else(|| PieceSetError::UnknownOccupancy(name.to_string()))?;
+                set = set.with_handicap(occupancy, vec![]);
+                section = Some(occupancy);
+                continue;
+            }
+
+            let piece = match line.split_once(':') {
+                Some((name, rows)) => Piece::Custom(CustomPiece::define(name.trim(), rows)?),
+                None => line
+                    .parse()
+                    .map_err(|_| PieceSetError::UnknownPiece(line.to_string()))?,
+            };
+            match section {
+                Some(occupancy) => set
+                    .handicaps
+                    .iter_mut()
+                    .find(|(o, _)| *o == occupancy)
+                    .expect("sections start a handicap")
+                    .1
+                    .push(piece),
+                None => set.pieces.push(piece),
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_sets_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../piece_sets");
+        let handicap = PieceSet::load(dir.join("handicap.txt")).unwrap();
+        assert_eq!(handicap.pieces(), Piece::ALL);
+        assert!(handicap.pieces_for(Occupancy::Blue).len() < handicap.pieces().len());
+        assert_eq!(handicap.pieces_for(Occupancy::Red), handicap.pieces());
+
+        let hexominoes = PieceSet::load(dir.join("hexominoes.txt")).unwrap();
+        assert_eq!(hexominoes.pieces().len(), 21 + 35);
+        let tetrominoes = PieceSet::load(dir.join("tetrominoes.txt")).unwrap();
+        assert_eq!(tetrominoes.pieces().len(), 5);
+    }
+
+    #[test]
+    fn custom_pieces_orient_like_piece_macro() {
+        let standard = [
+            (Piece::FourStairs, "XO_, _XX"),
+            (Piece::FiveF, "X__, XOX, _X_"),
+            (Piece::FiveW, "XX_, _OX, __X"),
+            (Piece::FiveY, "_X, XO, _X, _X"),
+        ];
+        for (piece, rows) in standard {
+            let name = format!("Same{}", piece.name());
+            let custom = Piece::Custom(CustomPiece::define(&name, rows).unwrap());
+            assert_eq!(custom.square_count(), piece.square_count());
+            assert_eq!(custom.orientations(), piece.orientations());
+            for orientation in Orientation::ALL {
+                let expected = piece.offsets(orientation);
+                assert_eq!(custom.offsets(orientation).offsets, expected.offsets);
+                assert_eq!(custom.offsets(orientation).pivot, expected.pivot);
+                assert_eq!(custom.mask(orientation), piece.mask(orientation));
+            }
+        }
+    }
+
+    #[test]
+    fn custom_names_stay_with_their_shape() {
+        let first = CustomPiece::define("Bar", "XOX, XXX").unwrap();
+        assert_eq!(CustomPiece::define("Bar", "XOX, XXX").unwrap(), first);
+        assert_eq!(CustomPiece::define("Bar", " XOX, XXX, ").unwrap(), first);
+        assert_eq!(CustomPiece::find("Bar"), Some(first));
+        assert!(matches!(
+            CustomPiece::define("Bar", "XO"),
+            Err(PieceSetError::NameTaken(_))
+        ));
+        assert!(matches!(
+            CustomPiece::define("FiveX", "XO"),
+            Err(PieceSetError::NameTaken(_))
+        ));
+    }
+
+    #[test]
+    fn names_have_to_fit_in_notation() {
+        for name in ["", "Two Words", "9Lives", "Tab\t", "Dash-Y", "pass"] {
+            assert!(matches!(
+                CustomPiece::define(name, "OX"),
+                Err(PieceSetError::BadName(_))
+            ));
+        }
+        assert!(CustomPiece::define("_Six9", "OX").is_ok());
+    }
+
+    #[test]
+    fn bad_masks_are_rejected() {
+        let error = |rows| CustomPiece::define("Bad", rows).unwrap_err();
+        assert!(matches!(
+            error("XA"),
+            PieceSetError::InvalidChar { c: 'A', .. }
+        ));
+        assert!(matches!(error("XO, X"), PieceSetError::UnevenRows(_)));
+        assert!(matches!(error("__, __"), PieceSetError::NoSquares(_)));
+        assert!(matches!(error("XX, XX"), PieceSetError::Pivot(_)));
+        assert!(matches!(error("OO"), PieceSetError::Pivot(_)));
+        assert!(matches!(error("XO_X"), PieceSetError::Disconnected(_)));
+        assert!(matches!(error("XXXXOXXXX"), PieceSetError::TooLarge(_)));
+        assert_eq!(CustomPiece::find("Bad"), None);
+    }
+
+    #[test]
+    fn sections_give_handicaps() {
+        let set: PieceSet = "One\nTwo\n\n[Green]\nFiveX\nL3: OX, X_\n".parse().unwrap();
+        assert_eq!(set.pieces(), [Piece::One, Piece::Two]);
+        let green = set.pieces_for(Occupancy::Green);
+        assert_eq!(green[0], Piece::FiveX);
+        assert_eq!(green[1].square_count(), 3);
+
+        assert!(matches!(
+            "[Purple]\nOne".parse::<PieceSet>(),
+            Err(PieceSetError::UnknownOccupancy(_))
+        ));
+        assert!(matches!(
+            "Seven".parse::<PieceSet>(),
+            Err(PieceSetError::UnknownPiece(_))
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use super::*;
+
+/// Replay files are [`GameRecord`]s written in the text notation
+pub const REPLAY_EXTENSION: &str = "wblk";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Notation(NotationError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "could not read the replay: {e}"),
+            ReplayError::Notation(e) => write!(f, "invalid replay: {e}"),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::Io(e) => Some(e),
+            ReplayError::Notation(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<NotationError> for ReplayError {
+    fn from(e: NotationError) -> Self {
+        ReplayError::Notation(e)
+    }
+}
+
+impl GameRecord {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<GameRecord, ReplayError> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+}
+
+/// Steps through a recorded game
+///
+/// The ply is how many of the record's turns have been played, from 0 for the empty
+/// board up to [`Replay::len`] for the final position.
+#[derive(Clone, Debug)]
+pub struct Replay<const D: usize = CLASSIC_DIM> {
+    record: GameRecord,
+    game: GameState<D>,
+    ply: usize,
+}
+
+impl<const D: usize> Replay<D> {
+    /// Checks every turn up front, then starts from the empty board
+    pub fn new(record: GameRecord) -> Result<Replay<D>, NotationError> {
+        let mut game = record.replay()?;
+        while game.undo() {}
+
+        Ok(Replay {
+            record,
+            game,
+            ply: 0,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay<D>, ReplayError> {
+        Ok(Replay::new(GameRecord::load(path)?)?)
+    }
+
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// The position at the current ply
+    pub fn game(&self) -> &GameState<D> {
+        &self.game
+    }
+
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn len(&self) -> usize {
+        self.record.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.record.turns.is_empty()
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.ply == self.len()
+    }
+
+    /// The turn that led to the current position, None at the start
+    pub fn last_turn(&self) -> Option<&Turn> {
+        self.ply.checked_sub(1).map(|i| &self.record.turns[i])
+    }
+
+    /// Returns false at the end of the game
+    pub fn step_forward(&mut self) -> bool {
+        if self.is_at_end() || !self.game.redo() {
+            return false;
+        }
+        self.ply += 1;
+        true
+    }
+
+    /// Returns false at the start of the game
+    pub fn step_back(&mut self) -> bool {
+        if self.ply == 0 || !self.game.undo() {
+            return false;
+        }
+        self.ply -= 1;
+        true
+    }
+
+    /// Plies past the end go to the end
+    pub fn jump_to(&mut self, ply: usize) {
+        let ply = ply.min(self.len());
+        while self.ply > ply && self.step_back() {}
+        while self.ply < ply && self.step_forward() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// A Duo game where Violet passes on their third turn, ending in forced passes
+    fn record_with_passes() -> GameRecord {
+        let mut game = GameState::<DUO_DIM>::with_variant(Variant::Duo);
+        let mut turns = 0;
+        play_randomly(&mut game, 0, |_| {
+            turns += 1;
+            turns > 4
+        });
+        game.pass(Occupancy::Violet).unwrap();
+        play_randomly(&mut game, 1, |_| false);
+        assert!(game.history().contains(&Turn::Passed {
+            occupancy: Occupancy::Orange,
+            forced: true
+        }));
+        GameRecord::from_game(&game)
+    }
+
+    /// What the replay should show after each of the record's turns
+    fn positions(record: &GameRecord) -> Vec<GameState<DUO_DIM>> {
+        let mut game = GameState::with_variant(record.variant);
+        let mut positions = vec![game.clone()];
+        for turn in &record.turns {
+            match *turn {
+                Turn::Placed { occupancy, mv } => game.play(occupancy, mv).unwrap(),
+                Turn::Passed { occupancy, .. } => game.pass(occupancy).unwrap(),
+            }
+            positions.push(game.clone());
+        }
+        positions
+    }
+
+    fn assert_shows(replay: &Replay<DUO_DIM>, position: &GameState<DUO_DIM>) {
+        let game = replay.game();
+        assert_eq!(game.board().occupancies, position.board().occupancies);
+        assert_eq!(game.history(), position.history());
+        assert_eq!(game.current_player(), position.current_player());
+    }
+
+    #[test]
+    fn steps_go_one_turn_at_a_time() {
+        let record = record_with_passes();
+        let positions = positions(&record);
+        let mut replay = Replay::<DUO_DIM>::new(record.clone()).unwrap();
+        assert_eq!(replay.len(), record.turns.len());
+
+        assert_eq!(replay.ply(), 0);
+        assert_eq!(replay.last_turn(), None);
+        assert!(!replay.step_back());
+        assert_shows(&replay, &positions[0]);
+
+        for (ply, (turn, position)) in record.turns.iter().zip(&positions[1..]).enumerate() {
+            assert!(replay.step_forward());
+            assert_eq!(replay.ply(), ply + 1);
+            assert_eq!(replay.last_turn(), Some(turn));
+            assert_shows(&replay, position);
+        }
+        assert!(replay.is_at_end());
+        assert!(replay.game().is_over());
+        assert!(!replay.step_forward());
+
+        for (ply, position) in positions.iter().enumerate().rev().skip(1) {
+            assert!(replay.step_back());
+            assert_eq!(replay.ply(), ply);
+            assert_shows(&replay, position);
+        }
+        assert!(!replay.step_back());
+    }
+
+    #[test]
+    fn jumps_land_on_the_same_positions_as_steps() {
+        let record = record_with_passes();
+        let positions = positions(&record);
+        let mut replay = Replay::<DUO_DIM>::new(record).unwrap();
+
+        // Just past Violet's pass, then back to before it
+        for ply in [6, 4, replay.len(), 5, 0] {
+            replay.jump_to(ply);
+            assert_eq!(replay.ply(), ply);
+            assert_shows(&replay, &positions[ply]);
+        }
+        replay.jump_to(replay.len() + 10);
+        assert!(replay.is_at_end());
+        assert_shows(&replay, positions.last().unwrap());
+    }
+
+    #[test]
+    fn replay_files_keep_their_piece_set() {
+        let pieces: PieceSet = "FourI\nLongBar: XXOXX\nFiveX\nOne\n[Orange]\nFourI\nOne"
+            .parse()
+            .unwrap();
+        let mut game =
+            GameState::<DUO_DIM>::with_piece_set(Variant::Duo, Variant::Duo.turn_order(), &pieces);
+        play_randomly(&mut game, 0, |_| false);
+
+        let text = GameRecord::from_game(&game).to_string();
+        assert!(
+            text.contains("[Pieces \"FourI; LongBar: XXOXX; FiveX; One; [Orange]; FourI; One\"]")
+        );
+        let record: GameRecord = text.parse().unwrap();
+        assert_eq!(record.pieces, pieces);
+        let mut replay = Replay::<DUO_DIM>::new(record).unwrap();
+        replay.jump_to(replay.len());
+        assert_eq!(replay.game(), &game);
+
+        let standard = GameRecord::from_game(&GameState::<DUO_DIM>::with_variant(Variant::Duo));
+        assert_eq!(standard.pieces, PieceSet::standard());
+        assert!(!standard.to_string().contains("Pieces"));
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Bonus for placing every piece under [`Scoring::Advanced`]
+pub const ALL_PLACED_BONUS: i32 = 15;
+/// Extra bonus under [`Scoring::Advanced`] when the last piece placed was [`Piece::One`]
+pub const MONOMINO_LAST_BONUS: i32 = 5;
+
+/// Both rules take a point off per square left in the inventory, so higher is better
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Scoring {
+    #[default]
+    Basic,
+    /// Adds the bonuses for placing every piece
+    Advanced,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub scoring: Scoring,
+    /// In turn order
+    pub scores: Vec<(Occupancy, i32)>,
+    /// Everyone sharing the best score
+    pub winners: Vec<Occupancy>,
+}
+
+impl GameResult {
+    pub fn is_tie(&self) -> bool {
+        self.winners.len() > 1
+    }
+
+    /// The sole winner, None on a tie
+    pub fn winner(&self) -> Option<Occupancy> {
+        match self.winners[..] {
+            [winner] => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn score(&self, occupancy: Occupancy) -> Option<i32> {
+        self.scores
+            .iter()
+            .find(|(o, _)| *o == occupancy)
+            .map(|(_, score)| *score)
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    pub fn score(&self, occupancy: Occupancy, scoring: Scoring) -> i32 {
+        let inventory = self.board().inventory(occupancy);
+        let unplaced: i32 = inventory.iter().map(|p| p.square_count() as i32).sum();
+
+        if scoring == Scoring::Basic || !inventory.is_empty() {
+            return -unplaced;
+        }
+
+        let last_placed = self.history().iter().rev().find_map(|turn| match turn {
+            Turn::Placed { occupancy: o, mv } if *o == occupancy => Some(mv.piece),
+            _ => None,
+        });
+        if last_placed == Some(Piece::One) {
+            ALL_PLACED_BONUS + MONOMINO_LAST_BONUS
+        } else {
+            ALL_PLACED_BONUS
+        }
+    }
+
+    /// Scores as they stand, whether or not the game is over
+    pub fn standings(&self, scoring: Scoring) -> GameResult {
+        let scores: Vec<_> = self
+            .turn_order()
+            .iter()
+            .map(|o| (*o, self.score(*o, scoring)))
+            .collect();
+        let best = scores.iter().map(|(_, score)| *score).max();
+        let winners = scores
+            .iter()
+            .filter(|(_, score)| Some(*score) == best)
+            .map(|(o, _)| *o)
+            .collect();
+
+        GameResult {
+            scoring,
+            scores,
+            winners,
+        }
+    }
+
+    /// None until the game is over
+    pub fn result(&self, scoring: Scoring) -> Option<GameResult> {
+        self.is_over().then(|| self.standings(scoring))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Occupancy::*;
+
+    /// Plays the occupancy's first legal move with the piece
+    fn place(game: &mut GameState, occupancy: Occupancy, piece: Piece) {
+        let moves = game.board().legal_moves(occupancy);
+        let mv = moves.iter().find(|mv| mv.piece == piece).unwrap();
+        game.play(occupancy, *mv).unwrap();
+    }
+
+    #[test]
+    fn squares_left_count_against_the_score() {
+        let mut game = GameState::new();
+        for scoring in [Scoring::Basic, Scoring::Advanced] {
+            assert_eq!(game.score(Blue, scoring), -89);
+        }
+        place(&mut game, Blue, Piece::FiveF);
+        place(&mut game, Yellow, Piece::Two);
+        assert_eq!(game.score(Blue, Scoring::Basic), -84);
+        assert_eq!(game.score(Yellow, Scoring::Advanced), -87);
+        assert_eq!(game.score(Red, Scoring::Basic), -89);
+    }
+
+    #[test]
+    fn placing_everything_earns_the_bonus() {
+        let mut game = GameState::new();
+        game.board_mut()
+            .inventories
+            .insert(Blue, vec![Piece::FiveF]);
+        place(&mut game, Blue, Piece::FiveF);
+        assert_eq!(game.score(Blue, Scoring::Basic), 0);
+        assert_eq!(game.score(Blue, Scoring::Advanced), ALL_PLACED_BONUS);
+    }
+
+    #[test]
+    fn placing_the_monomino_last_earns_more() {
+        let mut game = GameState::with_turn_order(Variant::Classic, vec![Blue]);
+        game.board_mut()
+            .inventories
+            .insert(Blue, vec![Piece::One, Piece::Two]);
+        place(&mut game, Blue, Piece::Two);
+        place(&mut game, Blue, Piece::One);
+        assert_eq!(game.score(Blue, Scoring::Basic), 0);
+        assert_eq!(
+            game.score(Blue, Scoring::Advanced),
+            ALL_PLACED_BONUS + MONOMINO_LAST_BONUS
+        );
+
+        let mut game = GameState::with_turn_order(Variant::Classic, vec![Blue]);
+        game.board_mut()
+            .inventories
+            .insert(Blue, vec![Piece::One, Piece::Two]);
+        place(&mut game, Blue, Piece::One);
+        place(&mut game, Blue, Piece::Two);
+        assert_eq!(game.score(Blue, Scoring::Advanced), ALL_PLACED_BONUS);
+    }
+
+    #[test]
+    fn everyone_sharing_the_best_score_wins() {
+        let mut game = GameState::new();
+        let standings = game.standings(Scoring::Basic);
+        assert_eq!(standings.winners, [Blue, Yellow, Red, Green]);
+        assert!(standings.is_tie());
+        assert_eq!(standings.winner(), None);
+        assert_eq!(game.result(Scoring::Basic), None);
+
+        place(&mut game, Blue, Piece::FiveF);
+        place(&mut game, Yellow, Piece::FiveV);
+        let standings = game.standings(Scoring::Basic);
+        assert_eq!(standings.winners, [Blue, Yellow]);
+        assert_eq!(standings.score(Red), Some(-89));
+        assert_eq!(standings.winner(), None);
+
+        place(&mut game, Red, Piece::One);
+        place(&mut game, Green, Piece::FourI);
+        place(&mut game, Blue, Piece::Two);
+        assert_eq!(game.standings(Scoring::Basic).winner(), Some(Blue));
+    }
+
+    #[test]
+    fn results_wait_for_the_end() {
+        let mut game = GameState::with_turn_order(Variant::Classic, vec![Blue, Yellow]);
+        place(&mut game, Blue, Piece::One);
+        game.pass(Yellow).unwrap();
+        assert_eq!(game.result(Scoring::Basic), None);
+        game.pass(Blue).unwrap();
+
+        let result = game.result(Scoring::Basic).unwrap();
+        assert_eq!(result.scores, [(Blue, -88), (Yellow, -89)]);
+        assert_eq!(result.winner(), Some(Blue));
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+use super::*;
+
+/// Why serialized data is not a position that could have been played
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// Not one inventory per player of the variant
+    BadInventories,
+    /// The history placement at this index could not have been made
+    IllegalPlacement(usize),
+    /// The redo placement at this index could not be made again
+    IllegalRedo(usize),
+    /// The occupancies are not what the history leaves on the board
+    WrongOccupancies,
+    BadTurnOrder,
+    BadPassed,
+    BadCurrentPlayer,
+    /// The turns do not match the board's placements
+    WrongHistory,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadInventories => write!(f, "inventories do not match the variant"),
+            SnapshotError::IllegalPlacement(i) => write!(f, "placement {i} is illegal"),
+            SnapshotError::IllegalRedo(i) => write!(f, "placement {i} to redo is illegal"),
+            SnapshotError::WrongOccupancies => {
+                write!(f, "occupancies do not match the placements")
+            }
+            SnapshotError::BadTurnOrder => write!(f, "invalid turn order"),
+            SnapshotError::BadPassed => write!(f, "passed players are not in the turn order"),
+            SnapshotError::BadCurrentPlayer => write!(f, "invalid current player"),
+            SnapshotError::WrongHistory => write!(f, "turns do not match the placements"),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+/// What a [`Board`] serializes as
+///
+/// Inventories are listed in the variant's turn order so the output does not depend on
+/// hashing. Reading it back replays the history, so only boards that could have been
+/// played are accepted.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename = "Board")]
+pub(super) struct BoardSnapshot<const D: usize> {
+    variant: Variant,
+    occupancies: Grid<Occupancy, D>,
+    inventories: Vec<(Occupancy, Vec<Piece>)>,
+    history: Vec<Placement>,
+    redo: Vec<Placement>,
+}
+
+impl<const D: usize> From<Board<D>> for BoardSnapshot<D> {
+    fn from(mut board: Board<D>) -> Self {
+        let inventories = board
+            .variant
+            .turn_order()
+            .into_iter()
+            .filter_map(|o| board.inventories.remove(&o).map(|inventory| (o, inventory)))
+            .collect();
+
+        BoardSnapshot {
+            variant: board.variant,
+            occupancies: board.occupancies,
+            inventories,
+            history: board.history,
+            redo: board.redo,
+        }
+    }
+}
+
+impl<const D: usize> TryFrom<BoardSnapshot<D>> for Board<D> {
+    type Error = SnapshotError;
+
+    fn try_from(snapshot: BoardSnapshot<D>) -> Result<Self, Self::Error> {
+        let BoardSnapshot {
+            variant,
+            occupancies,
+            inventories,
+            history,
+            redo,
+        } = snapshot;
+
+        let players = variant.turn_order();
+        if inventories.len() != players.len()
+            || !players
+                .iter()
+                .all(|player| inventories.iter().any(|(o, _)| o == player))
+        {
+            return Err(SnapshotError::BadInventories);
+        }
+
+        // Undoing the history gives back the inventories the game started with
+        let mut inventories: HashMap<_, _> = inventories.into_iter().collect();
+        for (i, placement) in history.iter().enumerate().rev() {
+            let pieces = inventories
+                .get_mut(&placement.occupancy)
+                .filter(|pieces| placement.inventory_index <= pieces.len())
+                .ok_or(SnapshotError::IllegalPlacement(i))?;
+            pieces.push(placement.mv.piece);
+            let last = pieces.len() - 1;
+            pieces.swap(placement.inventory_index, last);
+        }
+
+        let mut board = Board::with_variant(variant);
+        board.inventories = inventories;
+        board.hash = board.scan_zobrist_hash();
+        for (i, placement) in history.into_iter().enumerate() {
+            if !board.can_apply(placement) {
+                return Err(SnapshotError::IllegalPlacement(i));
+            }
+            board.apply(placement);
+        }
+        if board.occupancies != occupancies {
+            return Err(SnapshotError::WrongOccupancies);
+        }
+
+        let mut redone = board.clone();
+        for (i, placement) in redo.iter().enumerate().rev() {
+            if !redone.can_apply(*placement) {
+                return Err(SnapshotError::IllegalRedo(i));
+            }
+            redone.apply(*placement);
+        }
+        board.redo = redo;
+        Ok(board)
+    }
+}
+
+/// What a [`GameState`] deserializes from, checked before it becomes one
+#[derive(Deserialize)]
+#[serde(rename = "GameState")]
+pub(super) struct GameStateSnapshot<const D: usize> {
+    pub(super) board: Board<D>,
+    pub(super) turn_order: Vec<Occupancy>,
+    pub(super) current: Option<usize>,
+    pub(super) passed: Vec<Occupancy>,
+    pub(super) history: Vec<Turn>,
+    pub(super) redo: Vec<Turn>,
+}
+
+impl<const D: usize> GameStateSnapshot<D> {
+    /// Everything [`GameState`]'s methods rely on
+    pub(super) fn check(&self) -> Result<(), SnapshotError> {
+        if !self.board.variant().is_valid_turn_order(&self.turn_order) {
+            return Err(SnapshotError::BadTurnOrder);
+        }
+
+        let is_passed_valid = self.passed.iter().enumerate().all(|(i, occupancy)| {
+            self.turn_order.contains(occupancy) && !self.passed[..i].contains(occupancy)
+        });
+        if !is_passed_valid {
+            return Err(SnapshotError::BadPassed);
+        }
+
+        let is_current_valid = match self.current {
+            Some(i) => self
+                .turn_order
+                .get(i)
+                .is_some_and(|occupancy| !self.passed.contains(occupancy)),
+            None => self.passed.len() == self.turn_order.len(),
+        };
+        if !is_current_valid {
+            return Err(SnapshotError::BadCurrentPlayer);
+        }
+
+        let placed = self.history.iter().filter_map(|turn| match turn {
+            Turn::Placed { occupancy, mv } => Some((*occupancy, *mv)),
+            Turn::Passed { .. } => None,
+        });
+        let placements = self
+            .board
+            .placements()
+            .iter()
+            .map(|placement| (placement.occupancy, placement.mv));
+        let passes: Vec<_> = self
+            .history
+            .iter()
+            .filter(|turn| matches!(turn, Turn::Passed { .. }))
+            .map(Turn::occupancy)
+            .collect();
+        let is_history_valid = placed.eq(placements)
+            && passes.len() == self.passed.len()
+            && passes
+                .iter()
+                .all(|occupancy| self.passed.contains(occupancy));
+        if !is_history_valid {
+            return Err(SnapshotError::WrongHistory);
+        }
+
+        // Replaying the turns from the empty board shows which passes were forced and
+        // who is left to move
+        let mut start = self.board.clone();
+        while start.undo().is_some() {}
+        start.redo.clear();
+        let mut replayed = GameState::with_board(start, self.turn_order.clone());
+        for turn in &self.history {
+            let result = match *turn {
+                Turn::Placed { occupancy, mv } => replayed.play(occupancy, mv),
+                Turn::Passed {
+                    occupancy,
+                    forced: false,
+                } => replayed.pass(occupancy),
+                // Played along with the turn before
+                Turn::Passed { forced: true, .. } => Ok(()),
+            };
+            if result.is_err() {
+                return Err(SnapshotError::WrongHistory);
+            }
+        }
+        if replayed.history() != self.history {
+            return Err(SnapshotError::WrongHistory);
+        }
+        if replayed.current_player() != self.current.map(|i| self.turn_order[i]) {
+            return Err(SnapshotError::BadCurrentPlayer);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    /// Partway through a game, with a few moves undone so there is something to redo
+    fn game_with_redo() -> GameState<DUO_DIM> {
+        let mut game = GameState::with_variant(Variant::Duo);
+        let mut turns = 0;
+        play_randomly(&mut game, 0, |_| {
+            turns += 1;
+            turns > 12
+        });
+        for _ in 0..3 {
+            assert!(game.undo());
+        }
+        game
+    }
+
+    /// Reads back the game's JSON after `corrupt` has had a go at it
+    fn read_corrupted(game: GameState<DUO_DIM>, corrupt: impl FnOnce(&mut Value)) -> String {
+        let mut value = serde_json::to_value(game).unwrap();
+        corrupt(&mut value);
+        serde_json::from_value::<GameState<DUO_DIM>>(value)
+            .expect_err("corrupted snapshots are rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let mut game = game_with_redo();
+        let json: GameState<DUO_DIM> =
+            serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(json, game);
+        let bytes = bincode::serialize(&game).unwrap();
+        let mut binary: GameState<DUO_DIM> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(binary, game);
+
+        // The rebuilt board keeps its hash and can still redo
+        assert_eq!(binary.zobrist_hash(), game.zobrist_hash());
+        while game.redo() {
+            assert!(binary.redo());
+        }
+        assert_eq!(binary, game);
+    }
+
+    #[test]
+    fn boards_round_trip() {
+        let game = game_with_redo();
+        let board: Board<DUO_DIM> =
+            serde_json::from_str(&serde_json::to_string(game.board()).unwrap()).unwrap();
+        assert_eq!(&board, game.board());
+    }
+
+    #[test]
+    fn bad_players_are_rejected() {
+        let current = read_corrupted(game_with_redo(), |game| game["current"] = json!(9));
+        assert_eq!(current, SnapshotError::BadCurrentPlayer.to_string());
+        let empty = read_corrupted(game_with_redo(), |game| game["turn_order"] = json!([]));
+        assert_eq!(empty, SnapshotError::BadTurnOrder.to_string());
+        let foreign = read_corrupted(game_with_redo(), |game| {
+            game["turn_order"] = json!(["Violet", "Green"])
+        });
+        assert_eq!(foreign, SnapshotError::BadTurnOrder.to_string());
+        let passed = read_corrupted(game_with_redo(), |game| game["passed"] = json!(["Green"]));
+        assert_eq!(passed, SnapshotError::BadPassed.to_string());
+
+        // The other player has not passed, but it is not their turn
+        let other = read_corrupted(game_with_redo(), |game| {
+            let current = game["current"].as_u64().unwrap();
+            game["current"] = json!(1 - current);
+        });
+        assert_eq!(other, SnapshotError::BadCurrentPlayer.to_string());
+    }
+
+    #[test]
+    fn bad_placements_are_rejected() {
+        let index = read_corrupted(game_with_redo(), |game| {
+            game["board"]["history"][0]["inventory_index"] = json!(99)
+        });
+        assert_eq!(index, SnapshotError::IllegalPlacement(0).to_string());
+        let redo = read_corrupted(game_with_redo(), |game| {
+            let redo = game["board"]["redo"].as_array_mut().unwrap();
+            redo.swap(0, 2);
+        });
+        assert!(
+            redo.contains(&SnapshotError::IllegalRedo(2).to_string()),
+            "{redo}"
+        );
+        let occupancies = read_corrupted(game_with_redo(), |game| {
+            let history = game["board"]["history"].as_array_mut().unwrap();
+            history.pop();
+        });
+        assert_eq!(occupancies, SnapshotError::WrongOccupancies.to_string());
+        let turns = read_corrupted(game_with_redo(), |game| {
+            game["history"].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(turns, SnapshotError::WrongHistory.to_string());
+    }
+
+    #[test]
+    fn passes_must_match_the_position() {
+        let mut finished = GameState::with_variant(Variant::Duo);
+        play_randomly(&mut finished, 0, |_| false);
+        let explicit = read_corrupted(finished, |game| {
+            let history = game["history"].as_array_mut().unwrap();
+            let last = history.last_mut().unwrap();
+            assert_eq!(last["Passed"]["forced"], json!(true));
+            last["Passed"]["forced"] = json!(false);
+        });
+        assert_eq!(explicit, SnapshotError::WrongHistory.to_string());
+
+        let mut passed = GameState::with_variant(Variant::Duo);
+        passed.pass(Occupancy::Violet).unwrap();
+        let mv = passed.board().legal_moves(Occupancy::Orange)[0];
+        passed.play(Occupancy::Orange, mv).unwrap();
+        let forced = read_corrupted(passed, |game| {
+            game["history"][0]["Passed"]["forced"] = json!(true);
+        });
+        assert_eq!(forced, SnapshotError::WrongHistory.to_string());
+    }
+}
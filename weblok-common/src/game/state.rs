@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+use super::*;
+
+/// Something that happened on a player's turn
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Turn {
+    Placed {
+        occupancy: Occupancy,
+        mv: Move,
+    },
+    /// `forced` is true when the player had no legal move left
+    Passed {
+        occupancy: Occupancy,
+        forced: bool,
+    },
+}
+
+impl Turn {
+    pub fn occupancy(&self) -> Occupancy {
+        match self {
+            Turn::Placed { occupancy, .. } | Turn::Passed { occupancy, .. } => *occupancy,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveError {
+    GameOver,
+    NotYourTurn { expected: Occupancy, got: Occupancy },
+    PieceNotInInventory(Piece),
+    IllegalPlacement,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GameOver => write!(f, "the game is over"),
+            MoveError::NotYourTurn { expected, got } => {
+                write!(f, "it is {expected}'s turn, not {got}'s")
+            }
+            MoveError::PieceNotInInventory(piece) => {
+                write!(f, "{piece:?} has already been placed")
+            }
+            MoveError::IllegalPlacement => write!(f, "the piece cannot be placed there"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/// A [`Board`] played in turns
+///
+/// Players who pass, by choice or because they have no legal move left, are out for
+/// the rest of the game. The game is over once every player has passed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "GameStateSnapshot<D>")]
+pub struct GameState<const D: usize = CLASSIC_DIM> {
+    board: Board<D>,
+    turn_order: Vec<Occupancy>,
+    /// Index into turn_order, None once the game is over
+    current: Option<usize>,
+    passed: Vec<Occupancy>,
+    history: Vec<Turn>,
+    /// Undone moves and explicit passes, the next one to redo last
+    redo: Vec<Turn>,
+}
+
+impl<const D: usize> TryFrom<GameStateSnapshot<D>> for GameState<D> {
+    type Error = SnapshotError;
+
+    fn try_from(snapshot: GameStateSnapshot<D>) -> Result<Self, Self::Error> {
+        snapshot.check()?;
+        let GameStateSnapshot {
+            board,
+            turn_order,
+            current,
+            passed,
+            history,
+            redo,
+        } = snapshot;
+        Ok(GameState {
+            board,
+            turn_order,
+            current,
+            passed,
+            history,
+            redo,
+        })
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState {
+    pub fn new() -> GameState {
+        GameState::with_variant(Variant::Classic)
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    pub fn with_variant(variant: Variant) -> GameState<D> {
+        GameState::with_turn_order(variant, variant.turn_order())
+    }
+
+    /// Assumes [`Variant::is_valid_turn_order`]
+    pub fn with_turn_order(variant: Variant, turn_order: Vec<Occupancy>) -> GameState<D> {
+        GameState::with_piece_set(variant, turn_order, &PieceSet::standard())
+    }
+
+    /// Assumes [`Variant::is_valid_turn_order`]
+    pub fn with_piece_set(
+        variant: Variant,
+        turn_order: Vec<Occupancy>,
+        pieces: &PieceSet,
+    ) -> GameState<D> {
+        GameState::with_board(Board::with_piece_set(variant, pieces), turn_order)
+    }
+
+    /// Starts a game on `board`, which should have nothing placed yet
+    pub(super) fn with_board(board: Board<D>, turn_order: Vec<Occupancy>) -> GameState<D> {
+        let mut state = GameState {
+            board,
+            turn_order,
+            current: Some(0),
+            passed: vec![],
+            history: vec![],
+            redo: vec![],
+        };
+        state.skip_stuck_players();
+        state
+    }
+
+    pub fn board(&self) -> &Board<D> {
+        &self.board
+    }
+
+    /// Lets tests set up positions that are slow to reach by playing
+    #[cfg(test)]
+    pub(super) fn board_mut(&mut self) -> &mut Board<D> {
+        &mut self.board
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.board.variant()
+    }
+
+    pub fn turn_order(&self) -> &[Occupancy] {
+        &self.turn_order
+    }
+
+    pub fn history(&self) -> &[Turn] {
+        &self.history
+    }
+
+    /// The occupancy to move, None once the game is over
+    pub fn current_player(&self) -> Option<Occupancy> {
+        self.current.map(|i| self.turn_order[i])
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.current.is_none()
+    }
+
+    pub fn has_passed(&self, occupancy: Occupancy) -> bool {
+        self.passed.contains(&occupancy)
+    }
+
+    pub fn play(&mut self, occupancy: Occupancy, mv: Move) -> Result<(), MoveError> {
+        self.check_turn(occupancy)?;
+
+        if !self.board.inventory(occupancy).contains(&mv.piece) {
+            return Err(MoveError::PieceNotInInventory(mv.piece));
+        }
+        if !self.board.place_move(occupancy, mv) {
+            return Err(MoveError::IllegalPlacement);
+        }
+        self.history.push(Turn::Placed { occupancy, mv });
+        self.redo.clear();
+        self.advance();
+        Ok(())
+    }
+
+    /// Gives up the rest of the game for this occupancy
+    pub fn pass(&mut self, occupancy: Occupancy) -> Result<(), MoveError> {
+        self.check_turn(occupancy)?;
+        self.record_pass(occupancy, false);
+        self.redo.clear();
+        self.advance();
+        Ok(())
+    }
+
+    /// Takes back the last move or explicit pass, along with the forced passes after it
+    /// Returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(decision) = self
+            .history
+            .iter()
+            .rposition(|turn| !matches!(turn, Turn::Passed { forced: true, .. }))
+        else {
+            return false;
+        };
+
+        for turn in self.history.drain(decision..).rev().collect::<Vec<_>>() {
+            match turn {
+                Turn::Placed { .. } => {
+                    self.board.undo();
+                }
+                Turn::Passed { occupancy, .. } => self.passed.retain(|o| *o != occupancy),
+            }
+            self.current = self.turn_order.iter().position(|o| *o == turn.occupancy());
+            if !matches!(turn, Turn::Passed { forced: true, .. }) {
+                self.redo.push(turn);
+            }
+        }
+        true
+    }
+
+    /// Replays the last undone move or explicit pass
+    /// Returns false if there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(turn) = self.redo.pop() else {
+            return false;
+        };
+
+        // Replaying goes through play and pass, which would otherwise clear the rest
+        let redo = std::mem::take(&mut self.redo);
+        let result = match turn {
+            Turn::Placed { occupancy, mv } => self.play(occupancy, mv),
+            Turn::Passed { occupancy, .. } => self.pass(occupancy),
+        };
+        self.redo = redo;
+        result.is_ok()
+    }
+
+    fn check_turn(&self, occupancy: Occupancy) -> Result<(), MoveError> {
+        match self.current_player() {
+            None => Err(MoveError::GameOver),
+            Some(expected) if expected != occupancy => Err(MoveError::NotYourTurn {
+                expected,
+                got: occupancy,
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn record_pass(&mut self, occupancy: Occupancy, forced: bool) {
+        self.passed.push(occupancy);
+        self.history.push(Turn::Passed { occupancy, forced });
+    }
+
+    fn advance(&mut self) {
+        self.current = self.next_unpassed();
+        self.skip_stuck_players();
+    }
+
+    /// Records forced passes until someone can move or the game is over
+    fn skip_stuck_players(&mut self) {
+        while let Some(occupancy) = self.current_player() {
+            if self.board.has_legal_move(occupancy) {
+                return;
+            }
+            self.record_pass(occupancy, true);
+            self.current = self.next_unpassed();
+        }
+    }
+
+    /// Index of the next player in turn order who has not passed
+    fn next_unpassed(&self) -> Option<usize> {
+        let current = self.current?;
+        let len = self.turn_order.len();
+        (1..=len)
+            .map(|step| (current + step) % len)
+            .find(|i| !self.has_passed(self.turn_order[*i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+    use Occupancy::*;
+
+    #[test]
+    fn only_the_current_player_moves() {
+        let mut game = GameState::new();
+        let mv = game.board().legal_moves(Blue)[0];
+        let not_yellow = Err(MoveError::NotYourTurn {
+            expected: Blue,
+            got: Yellow,
+        });
+        assert_eq!(game.play(Yellow, mv), not_yellow);
+        assert_eq!(game.pass(Yellow), not_yellow);
+        assert!(game.history().is_empty());
+
+        assert_eq!(game.play(Blue, mv), Ok(()));
+        assert_eq!(game.current_player(), Some(Yellow));
+        assert_eq!(
+            game.play(Blue, mv),
+            Err(MoveError::NotYourTurn {
+                expected: Yellow,
+                got: Blue
+            })
+        );
+    }
+
+    #[test]
+    fn placements_are_checked() {
+        let mut game = GameState::new();
+        let mv = game.board().legal_moves(Blue)[0];
+        let mut off_board = mv;
+        off_board.col = -10;
+        assert_eq!(game.play(Blue, off_board), Err(MoveError::IllegalPlacement));
+        assert_eq!(game.current_player(), Some(Blue));
+
+        game.play(Blue, mv).unwrap();
+        game.board.inventories.insert(Yellow, vec![Piece::One]);
+        let missing = Move::new(Piece::Two, Orientation::default(), 0, 0);
+        assert_eq!(
+            game.play(Yellow, missing),
+            Err(MoveError::PieceNotInInventory(Piece::Two))
+        );
+    }
+
+    #[test]
+    fn passed_players_are_out() {
+        let mut game = GameState::new();
+        assert_eq!(game.pass(Blue), Ok(()));
+        assert_eq!(
+            game.history(),
+            [Turn::Passed {
+                occupancy: Blue,
+                forced: false
+            }]
+        );
+        assert!(game.has_passed(Blue));
+
+        for occupancy in [Yellow, Red, Green] {
+            let mv = game.board().legal_moves(occupancy)[0];
+            game.play(occupancy, mv).unwrap();
+        }
+        assert_eq!(game.current_player(), Some(Yellow));
+        assert_eq!(
+            game.pass(Blue),
+            Err(MoveError::NotYourTurn {
+                expected: Yellow,
+                got: Blue
+            })
+        );
+    }
+
+    #[test]
+    fn stuck_players_are_passed_for() {
+        let mut game = GameState::new();
+        game.board.inventories.insert(Yellow, vec![]);
+        let mv = game.board().legal_moves(Blue)[0];
+        game.play(Blue, mv).unwrap();
+
+        assert_eq!(
+            game.history()[1],
+            Turn::Passed {
+                occupancy: Yellow,
+                forced: true
+            }
+        );
+        assert_eq!(game.current_player(), Some(Red));
+        assert_eq!(
+            game.pass(Yellow),
+            Err(MoveError::NotYourTurn {
+                expected: Red,
+                got: Yellow
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_is_played_after_the_game_is_over() {
+        let mut game = GameState::new();
+        play_randomly(&mut game, 0, |_| false);
+        assert!(game.is_over());
+        assert_eq!(game.current_player(), None);
+        assert!(game.turn_order().iter().all(|o| game.has_passed(*o)));
+
+        let history = game.history().len();
+        let mv = Move::new(Piece::One, Orientation::default(), 0, 0);
+        assert_eq!(game.play(Blue, mv), Err(MoveError::GameOver));
+        assert_eq!(game.pass(Blue), Err(MoveError::GameOver));
+        assert_eq!(game.history().len(), history);
+    }
+
+    #[test]
+    fn undo_takes_back_forced_passes_with_the_turn_before() {
+        let mut game = GameState::new();
+        game.board_mut().inventories.insert(Yellow, vec![]);
+        let mv = game.board().legal_moves(Blue)[0];
+        game.play(Blue, mv).unwrap();
+        let played = game.clone();
+        assert_eq!(game.history().len(), 2);
+
+        assert!(game.undo());
+        assert!(game.history().is_empty());
+        assert_eq!(game.current_player(), Some(Blue));
+        assert!(!game.has_passed(Yellow));
+        assert!(!game.undo());
+
+        assert!(game.redo());
+        assert_eq!(game, played);
+        assert!(!game.redo());
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Rules that differ between ways of playing
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Variant {
+    /// Four players, each opening from their own corner of the board
+    #[default]
+    Classic,
+    /// Two players on a 14x14 board, opening from fixed points near the center
+    Duo,
+}
+
+impl Variant {
+    /// The size the variant is normally played at
+    pub fn board_size(self) -> usize {
+        match self {
+            Variant::Classic => CLASSIC_DIM,
+            Variant::Duo => DUO_DIM,
+        }
+    }
+
+    /// The players, in turn order
+    pub fn turn_order(self) -> Vec<Occupancy> {
+        match self {
+            Variant::Classic => vec![
+                Occupancy::Blue,
+                Occupancy::Yellow,
+                Occupancy::Red,
+                Occupancy::Green,
+            ],
+            Variant::Duo => vec![Occupancy::Violet, Occupancy::Orange],
+        }
+    }
+
+    /// Whether the order lists some of the variant's players, each once
+    pub fn is_valid_turn_order(self, order: &[Occupancy]) -> bool {
+        let players = self.turn_order();
+        !order.is_empty()
+            && order.iter().enumerate().all(|(i, occupancy)| {
+                players.contains(occupancy) && !order[..i].contains(occupancy)
+            })
+    }
+
+    /// How games of this variant are scored unless agreed otherwise
+    pub fn scoring(self) -> Scoring {
+        match self {
+            Variant::Classic => Scoring::Basic,
+            Variant::Duo => Scoring::Advanced,
+        }
+    }
+
+    /// The cell the occupancy's first piece has to cover on a board of the given size
+    /// None if the occupancy does not play in this variant
+    pub fn start_point(self, occupancy: Occupancy, size: usize) -> Option<(i8, i8)> {
+        let last = size as i8 - 1;
+        // Closer to the corners on boards too small to keep the two points apart
+        let inset = 4.min((last - 1) / 2);
+        match (self, occupancy) {
+            // Clockwise from the top left, following turn order
+            (Variant::Classic, Occupancy::Blue) => Some((0, 0)),
+            (Variant::Classic, Occupancy::Yellow) => Some((last, 0)),
+            (Variant::Classic, Occupancy::Red) => Some((last, last)),
+            (Variant::Classic, Occupancy::Green) => Some((0, last)),
+            // Five cells in from opposite corners, (4, 4) and (9, 9) on a 14x14 board
+            (Variant::Duo, Occupancy::Violet) => Some((inset, inset)),
+            (Variant::Duo, Occupancy::Orange) => Some((last - inset, last - inset)),
+            _ => None,
+        }
+    }
+}
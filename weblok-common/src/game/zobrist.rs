@@ -0,0 +1,162 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use super::*;
+
+/// The widest board a [`Bitboard`] row can hold
+const MAX_DIM: usize = 32;
+
+static CELL_KEYS: [u64; MAX_DIM * MAX_DIM * OCCUPANCY_COUNT] = keys(1);
+static PIECE_KEYS: [u64; Piece::ALL.len() * OCCUPANCY_COUNT] = keys(2);
+static TURN_KEYS: [u64; OCCUPANCY_COUNT] = keys(3);
+static PASSED_KEYS: [u64; OCCUPANCY_COUNT] = keys(4);
+
+/// Fixed rather than seeded, so hashes are the same from run to run
+const fn keys<const N: usize>(table: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        keys[i] = splitmix64(table << 32 | i as u64);
+        i += 1;
+    }
+    keys
+}
+
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn cell_key(occupancy: Occupancy, col: i8, row: i8) -> u64 {
+    CELL_KEYS[(row as usize * MAX_DIM + col as usize) * OCCUPANCY_COUNT + occupancy as usize]
+}
+
+/// Sets can hold a piece more than once, so each copy gets its own key rather than two
+/// copies cancelling out
+fn piece_key(occupancy: Occupancy, piece: Piece, copy: usize) -> u64 {
+    match piece.index() {
+        Some(i) => splitmix64(PIECE_KEYS[i * OCCUPANCY_COUNT + occupancy as usize] ^ copy as u64),
+        None => {
+            // Custom pieces hash by name
+            let mut hasher = DefaultHasher::new();
+            (occupancy, piece, copy).hash(&mut hasher);
+            splitmix64(hasher.finish())
+        }
+    }
+}
+
+impl<const D: usize> Board<D> {
+    /// A Zobrist hash of the cells and inventories
+    ///
+    /// Whose turn it is and who has passed are up to the [`GameState`], which adds them
+    /// in [`GameState::zobrist_hash`].
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Flips the placed cells in and the piece out of the hash, or back on undo
+    /// Expects the inventory without the piece, so the copy taken is the one after those
+    /// still left
+    pub(super) fn toggle_zobrist_placement(&mut self, occupancy: Occupancy, mv: Move) {
+        for (x, y) in mv.piece.mask(mv.orientation).offsets() {
+            self.hash ^= cell_key(occupancy, mv.col + x, mv.row + y);
+        }
+        let copy = self.inventories[&occupancy]
+            .iter()
+            .filter(|piece| **piece == mv.piece)
+            .count();
+        self.hash ^= piece_key(occupancy, mv.piece, copy);
+    }
+
+    /// For boards built without going through apply
+    pub(super) fn scan_zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (occupancy, pieces) in &self.inventories {
+            for (col, row) in self.bitboard(*occupancy).iter() {
+                hash ^= cell_key(*occupancy, col, row);
+            }
+            for (i, piece) in pieces.iter().enumerate() {
+                let copy = pieces[..i].iter().filter(|other| *other == piece).count();
+                hash ^= piece_key(*occupancy, *piece, copy);
+            }
+        }
+        hash
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// The board's hash along with the player to move and who has passed
+    /// Takes a few XORs on top of the board's, no rescanning
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = self.board().zobrist_hash();
+        if let Some(occupancy) = self.current_player() {
+            hash ^= TURN_KEYS[occupancy as usize];
+        }
+        for occupancy in self.turn_order() {
+            if self.has_passed(*occupancy) {
+                hash ^= PASSED_KEYS[*occupancy as usize];
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::play_randomly;
+
+    fn board_with(pieces: &str) -> Board<DUO_DIM> {
+        Board::with_piece_set(Variant::Duo, &pieces.parse().unwrap())
+    }
+
+    #[test]
+    fn duplicate_pieces_do_not_cancel_out() {
+        let single = board_with("One");
+        let doubled = board_with("FourI\nFourI\nOne");
+        assert_ne!(single.zobrist_hash(), doubled.zobrist_hash());
+        assert_ne!(
+            doubled.zobrist_hash(),
+            board_with("FourI\nOne").zobrist_hash()
+        );
+    }
+
+    #[test]
+    fn inventory_order_does_not_change_the_hash() {
+        let a = board_with("FourI\nOne\nFourI\nTwo");
+        let b = board_with("Two\nFourI\nFourI\nOne");
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_rescan() {
+        let pieces = "FourI\nFourI\nOne\nOne\nFiveF\nThreeL\nFourI"
+            .parse()
+            .unwrap();
+        for seed in 0..5 {
+            let turn_order = Variant::Duo.turn_order();
+            let mut game = GameState::<DUO_DIM>::with_piece_set(Variant::Duo, turn_order, &pieces);
+            play_randomly(&mut game, seed, |game| {
+                assert_eq!(game.board().hash, game.board().scan_zobrist_hash());
+                false
+            });
+            while game.undo() {
+                assert_eq!(game.board().hash, game.board().scan_zobrist_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn passing_changes_only_the_game_hash() {
+        let mut game = GameState::<DUO_DIM>::with_variant(Variant::Duo);
+        let (board_hash, game_hash) = (game.board().zobrist_hash(), game.zobrist_hash());
+        let occupancy = game.current_player().unwrap();
+        game.pass(occupancy).unwrap();
+        assert_eq!(game.board().zobrist_hash(), board_hash);
+        assert_ne!(game.zobrist_hash(), game_hash);
+    }
+}
@@ -1,4 +1,6 @@
 mod api;
+pub mod bot;
+pub mod game;
 mod names;
 pub use api::*;
 
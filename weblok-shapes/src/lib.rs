@@ -0,0 +1,310 @@
+//! Piece geometry shared by the `piece!` macro and pieces defined at runtime
+//!
+//! A mask is written as rows of `X` for a square, `O` for the square the piece is held by
+//! and `_` for an empty cell, e.g. `["XOX", "_X_"]`.
+
+use std::{error::Error, fmt};
+
+/// Largest width or height of a piece's bounding box
+pub const MAX_PIECE_DIM: usize = 8;
+
+/// Rows of the neighbor masks, one more than the piece on each side
+pub const NEIGHBOR_DIM: usize = MAX_PIECE_DIM + 2;
+
+/// Rotations first, then the same rotations of the piece mirrored left to right
+pub const ORIENTATION_COUNT: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceChar {
+    Solid,
+    Pivot,
+    Empty,
+}
+
+impl PieceChar {
+    pub fn from_char(c: char) -> Option<PieceChar> {
+        match c {
+            'X' => Some(PieceChar::Solid),
+            'O' => Some(PieceChar::Pivot),
+            '_' => Some(PieceChar::Empty),
+            _ => None,
+        }
+    }
+}
+
+/// What's wrong with a mask, along with the row to blame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    InvalidChar { row: usize, c: char },
+    UnevenRows { row: usize, len: usize },
+    TooLarge { row: usize },
+    NoSquares,
+    NoPivot,
+    ExtraPivot { row: usize },
+    Disconnected { row: usize },
+}
+
+impl ShapeError {
+    pub fn row(&self) -> usize {
+        match self {
+            ShapeError::InvalidChar { row, .. }
+            | ShapeError::UnevenRows { row, .. }
+            | ShapeError::TooLarge { row }
+            | ShapeError::ExtraPivot { row }
+            | ShapeError::Disconnected { row } => *row,
+            ShapeError::NoSquares | ShapeError::NoPivot => 0,
+        }
+    }
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::InvalidChar { c, .. } => write!(f, "Invalid char {c}"),
+            ShapeError::UnevenRows { len, .. } => {
+                write!(f, "Expected every row to be {len} long")
+            }
+            ShapeError::TooLarge { .. } => write!(
+                f,
+                "Pieces can be at most {MAX_PIECE_DIM} squares wide and tall"
+            ),
+            ShapeError::NoSquares => write!(f, "Expected at least one square"),
+            ShapeError::NoPivot => write!(f, "Expected an O pivot square"),
+            ShapeError::ExtraPivot { .. } => write!(f, "Expected a single O pivot square"),
+            ShapeError::Disconnected { .. } => {
+                write!(f, "Every square has to share an edge with another one")
+            }
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+/// One orientation of a piece
+/// Offsets start at (0, 0), so empty rows and columns around the squares don't count
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrientedShape {
+    /// (x, y), listed column by column
+    pub offsets: Vec<(i8, i8)>,
+    pub pivot: (i8, i8),
+}
+
+/// A checked mask in every orientation
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub square_count: u8,
+    /// In [`ORIENTATION_COUNT`] order
+    pub orientations: [OrientedShape; ORIENTATION_COUNT],
+    /// For each orientation, the first one covering the same squares
+    pub canonical: [usize; ORIENTATION_COUNT],
+}
+
+impl Shape {
+    /// Checks the mask, then builds all of its orientations
+    pub fn parse<S: AsRef<str>>(rows: &[S]) -> Result<Shape, ShapeError> {
+        let mask: Vec<Vec<PieceChar>> = rows
+            .iter()
+            .enumerate()
+            .map(|(row, chars)| {
+                chars
+                    .as_ref()
+                    .chars()
+                    .map(|c| PieceChar::from_char(c).ok_or(ShapeError::InvalidChar { row, c }))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let len = mask.iter().map(Vec::len).max().unwrap_or(0);
+        if let Some(row) = mask.iter().position(|chars| chars.len() != len) {
+            return Err(ShapeError::UnevenRows { row, len });
+        }
+        if len > MAX_PIECE_DIM || mask.len() > MAX_PIECE_DIM {
+            let row = if mask.len() > MAX_PIECE_DIM {
+                MAX_PIECE_DIM
+            } else {
+                0
+            };
+            return Err(ShapeError::TooLarge { row });
+        }
+
+        let squares: Vec<(usize, usize)> = mask
+            .iter()
+            .enumerate()
+            .flat_map(|(y, chars)| {
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c != PieceChar::Empty)
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect();
+        if squares.is_empty() {
+            return Err(ShapeError::NoSquares);
+        }
+
+        let pivot_rows: Vec<_> = squares
+            .iter()
+            .filter(|(x, y)| mask[*y][*x] == PieceChar::Pivot)
+            .map(|(_, y)| *y)
+            .collect();
+        match pivot_rows[..] {
+            [] => return Err(ShapeError::NoPivot),
+            [_] => {}
+            [_, row, ..] => return Err(ShapeError::ExtraPivot { row }),
+        }
+
+        if let Some((_, row)) = first_disconnected(&squares) {
+            return Err(ShapeError::Disconnected { row });
+        }
+
+        let orientations = orientation_masks(&mask).map(|mask| oriented_shape(&mask));
+        let canonical = std::array::from_fn(|i| {
+            orientations
+                .iter()
+                .position(|other| other.offsets == orientations[i].offsets)
+                .unwrap()
+        });
+        Ok(Shape {
+            square_count: squares.len() as u8,
+            orientations,
+            canonical,
+        })
+    }
+}
+
+/// The bits of a piece mask, bit x of row y set for the offset (x, y)
+/// Neighbors are shifted one right and down, so bit 0 of row 0 is the offset (-1, -1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaskBits {
+    pub rows: [u8; MAX_PIECE_DIM],
+    /// Cells sharing an edge with the piece
+    pub side_neighbors: [u16; NEIGHBOR_DIM],
+    /// Cells touching the piece only at a corner
+    pub corner_neighbors: [u16; NEIGHBOR_DIM],
+    pub width: u8,
+    pub height: u8,
+}
+
+impl MaskBits {
+    /// Assumes the offsets are non-negative and fit in [`MAX_PIECE_DIM`]
+    pub fn from_offsets(offsets: &[(i8, i8)]) -> MaskBits {
+        let mut mask = MaskBits {
+            rows: [0; MAX_PIECE_DIM],
+            side_neighbors: [0; NEIGHBOR_DIM],
+            corner_neighbors: [0; NEIGHBOR_DIM],
+            width: 0,
+            height: 0,
+        };
+        for (x, y) in offsets {
+            mask.rows[*y as usize] |= 1 << x;
+            mask.width = mask.width.max(*x as u8 + 1);
+            mask.height = mask.height.max(*y as u8 + 1);
+        }
+
+        let covers = |x: i8, y: i8| offsets.contains(&(x, y));
+        for y in -1..=mask.height as i8 {
+            for x in -1..=mask.width as i8 {
+                if covers(x, y) {
+                    continue;
+                }
+                let bit = 1 << (x + 1);
+                if covers(x - 1, y) || covers(x + 1, y) || covers(x, y - 1) || covers(x, y + 1) {
+                    mask.side_neighbors[(y + 1) as usize] |= bit;
+                } else if covers(x - 1, y - 1)
+                    || covers(x + 1, y - 1)
+                    || covers(x - 1, y + 1)
+                    || covers(x + 1, y + 1)
+                {
+                    mask.corner_neighbors[(y + 1) as usize] |= bit;
+                }
+            }
+        }
+        mask
+    }
+}
+
+/// In [`ORIENTATION_COUNT`] order
+fn orientation_masks(mask: &[Vec<PieceChar>]) -> [Vec<Vec<PieceChar>>; ORIENTATION_COUNT] {
+    let [[zero, ninety, one_eighty, two_seventy], [f_zero, f_ninety, f_one_eighty, f_two_seventy]] =
+        [mask.to_vec(), flip_piece(mask)].map(|mask| {
+            let ninety = rot_piece_90(&mask);
+            let one_eighty = rot_piece_180(&mask);
+            let two_seventy = rot_piece_180(&ninety);
+            [mask, ninety, one_eighty, two_seventy]
+        });
+    [
+        zero,
+        ninety,
+        one_eighty,
+        two_seventy,
+        f_zero,
+        f_ninety,
+        f_one_eighty,
+        f_two_seventy,
+    ]
+}
+
+fn oriented_shape(mask: &[Vec<PieceChar>]) -> OrientedShape {
+    let mut offsets = vec![];
+    let mut pivot = (0, 0);
+    for col in 0..mask[0].len() {
+        for (row, chars) in mask.iter().enumerate() {
+            if chars[col] != PieceChar::Empty {
+                offsets.push((col as i8, row as i8));
+            }
+            if chars[col] == PieceChar::Pivot {
+                pivot = (col as i8, row as i8);
+            }
+        }
+    }
+
+    let min_x = offsets.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = offsets.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    for (x, y) in offsets.iter_mut().chain([&mut pivot]) {
+        *x -= min_x;
+        *y -= min_y;
+    }
+    OrientedShape { offsets, pivot }
+}
+
+/// A square that can't be reached from the first one, walking across shared edges
+fn first_disconnected(squares: &[(usize, usize)]) -> Option<(usize, usize)> {
+    let mut reached = vec![squares[0]];
+    let mut i = 0;
+    while i < reached.len() {
+        let (x, y) = reached[i];
+        for square in squares {
+            if x.abs_diff(square.0) + y.abs_diff(square.1) == 1 && !reached.contains(square) {
+                reached.push(*square);
+            }
+        }
+        i += 1;
+    }
+    squares
+        .iter()
+        .find(|square| !reached.contains(square))
+        .copied()
+}
+
+/// Mirrors the piece left to right
+fn flip_piece<T: Copy>(mask: &[Vec<T>]) -> Vec<Vec<T>> {
+    mask.iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .collect()
+}
+
+/// Requires every row to be the same length
+fn rot_piece_90<T: Copy>(mask: &[Vec<T>]) -> Vec<Vec<T>> {
+    let col_count = mask[0].len();
+    (0..col_count)
+        .rev()
+        .map(|col| mask.iter().map(|row| row[col]).collect())
+        .collect()
+}
+
+fn rot_piece_180<T: Copy>(mask: &[Vec<T>]) -> Vec<Vec<T>> {
+    mask.iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .rev()
+        .collect()
+}